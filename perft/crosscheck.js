@@ -0,0 +1,518 @@
+// independent perft counter used to cross check the perft suites of this directory
+//
+// a plain mailbox move generator written from the rules, sharing no code with the engine,
+// covers Standard, Atomic and Eightpiece
+//
+// usage : node perft/crosscheck.js <variant> <max nodes> < suite.epd
+// prints the lines whose counts agree, and a FAILED line for every disagreement
+
+"use strict";
+
+const WHITE = 1;
+const BLACK = 0;
+
+const PAWN = 1;
+const KNIGHT = 2;
+const BISHOP = 3;
+const ROOK = 4;
+const QUEEN = 5;
+const KING = 6;
+const SENTRY = 7;
+const JAILER = 8;
+const LANCER = 9;
+
+// lancer directions in the order N NE E SE S SW W NW, as rank and file steps
+const LANCER_DIRS = [
+  [1, 0],
+  [1, 1],
+  [0, 1],
+  [-1, 1],
+  [-1, 0],
+  [-1, -1],
+  [0, -1],
+  [1, -1],
+];
+const LANCER_NAMES = ["n", "ne", "e", "se", "s", "sw", "w", "nw"];
+
+const ROOK_DIRS = [
+  [1, 0],
+  [-1, 0],
+  [0, 1],
+  [0, -1],
+];
+const BISHOP_DIRS = [
+  [1, 1],
+  [1, -1],
+  [-1, 1],
+  [-1, -1],
+];
+const QUEEN_DIRS = ROOK_DIRS.concat(BISHOP_DIRS);
+const KNIGHT_JUMPS = [
+  [1, 2],
+  [2, 1],
+  [2, -1],
+  [1, -2],
+  [-1, -2],
+  [-2, -1],
+  [-2, 1],
+  [-1, 2],
+];
+
+const mkPiece = (type, col, dir) => (type << 4) | ((dir || 0) << 1) | col;
+const typeOf = (p) => p >> 4;
+const colorOf = (p) => p & 1;
+const dirOf = (p) => (p >> 1) & 7;
+
+const sq = (r, f) => r * 8 + f;
+const rankOf = (s) => s >> 3;
+const fileOf = (s) => s & 7;
+const onBoard = (r, f) => r >= 0 && r < 8 && f >= 0 && f < 8;
+
+const SYMBOLS = { p: PAWN, n: KNIGHT, b: BISHOP, r: ROOK, q: QUEEN, k: KING, s: SENTRY, j: JAILER };
+
+function parseSquare(str) {
+  return sq(str.charCodeAt(1) - 49, str.charCodeAt(0) - 97);
+}
+
+function parseFen(variant, fen) {
+  const parts = fen.trim().split(/\s+/);
+  const board = new Array(64).fill(0);
+  let r = 7;
+  let f = 0;
+  const pl = parts[0];
+  for (let i = 0; i < pl.length; i++) {
+    const c = pl[i];
+    if (c === "/") {
+      r--;
+      f = 0;
+    } else if (c >= "1" && c <= "8") {
+      f += c.charCodeAt(0) - 48;
+    } else if (c === "l" || c === "L") {
+      const col = c === "L" ? WHITE : BLACK;
+      let name = pl[i + 1];
+      i++;
+      if ((name === "n" || name === "s") && (pl[i + 1] === "e" || pl[i + 1] === "w")) {
+        name += pl[i + 1];
+        i++;
+      }
+      const dir = LANCER_NAMES.indexOf(name);
+      if (dir < 0) throw new Error("invalid lancer in " + fen);
+      board[sq(r, f++)] = mkPiece(LANCER, col, dir);
+    } else {
+      const type = SYMBOLS[c.toLowerCase()];
+      if (!type) throw new Error("invalid symbol " + c);
+      board[sq(r, f++)] = mkPiece(type, c === c.toUpperCase() ? WHITE : BLACK);
+    }
+  }
+  const pos = {
+    variant,
+    board,
+    turn: parts[1] === "w" ? WHITE : BLACK,
+    castle: { K: false, Q: false, k: false, q: false },
+    ep: parts[3] === "-" ? -1 : parseSquare(parts[3]),
+    disabled: null,
+  };
+  for (const c of parts[2]) if (c in pos.castle) pos.castle[c] = true;
+  if (variant === "eightpiece" && parts.length > 6 && parts[6] !== "-") {
+    pos.disabled = [parseSquare(parts[6].slice(0, 2)), parseSquare(parts[6].slice(2, 4))];
+  }
+  return pos;
+}
+
+function kingSquare(pos, col) {
+  const k = mkPiece(KING, col);
+  return pos.board.indexOf(k);
+}
+
+// squares of non king pieces of col next to an enemy jailer
+function jailed(pos, col) {
+  const out = new Array(64).fill(false);
+  if (pos.variant !== "eightpiece") return out;
+  const jailer = mkPiece(JAILER, 1 - col);
+  for (let s = 0; s < 64; s++) {
+    if (pos.board[s] !== jailer) continue;
+    for (const [dr, df] of QUEEN_DIRS) {
+      const r = rankOf(s) + dr;
+      const f = fileOf(s) + df;
+      if (!onBoard(r, f)) continue;
+      const p = pos.board[sq(r, f)];
+      if (p && colorOf(p) === col && typeOf(p) !== KING) out[sq(r, f)] = true;
+    }
+  }
+  return out;
+}
+
+// tells whether col attacks square t, that is could capture a piece standing there
+function attacks(pos, col, t) {
+  const b = pos.board;
+  const jail = jailed(pos, col);
+  for (let s = 0; s < 64; s++) {
+    const p = b[s];
+    if (!p || colorOf(p) !== col || jail[s]) continue;
+    const r = rankOf(s);
+    const f = fileOf(s);
+    const dr = rankOf(t) - r;
+    const df = fileOf(t) - f;
+    switch (typeOf(p)) {
+      case PAWN:
+        if (dr === (col === WHITE ? 1 : -1) && Math.abs(df) === 1) return true;
+        break;
+      case KNIGHT:
+        if ((Math.abs(dr) === 1 && Math.abs(df) === 2) || (Math.abs(dr) === 2 && Math.abs(df) === 1))
+          return true;
+        break;
+      case KING:
+        if (pos.variant !== "atomic" && Math.max(Math.abs(dr), Math.abs(df)) === 1) return true;
+        break;
+      case BISHOP:
+      case ROOK:
+      case QUEEN: {
+        const dirs = typeOf(p) === BISHOP ? BISHOP_DIRS : typeOf(p) === ROOK ? ROOK_DIRS : QUEEN_DIRS;
+        for (const [ddr, ddf] of dirs) {
+          let rr = r + ddr;
+          let ff = f + ddf;
+          while (onBoard(rr, ff)) {
+            if (sq(rr, ff) === t) return true;
+            if (b[sq(rr, ff)]) break;
+            rr += ddr;
+            ff += ddf;
+          }
+        }
+        break;
+      }
+      case LANCER: {
+        // a lancer passes over pieces of its own color
+        const [ddr, ddf] = LANCER_DIRS[dirOf(p)];
+        let rr = r + ddr;
+        let ff = f + ddf;
+        while (onBoard(rr, ff)) {
+          if (sq(rr, ff) === t) return true;
+          const q = b[sq(rr, ff)];
+          if (q && colorOf(q) !== col) break;
+          rr += ddr;
+          ff += ddf;
+        }
+        break;
+      }
+      default:
+        // sentries and jailers do not capture
+        break;
+    }
+  }
+  return false;
+}
+
+function inCheck(pos, col) {
+  const k = kingSquare(pos, col);
+  if (k < 0) return false;
+  if (pos.variant === "atomic") {
+    const ek = kingSquare(pos, 1 - col);
+    if (ek >= 0 && Math.max(Math.abs(rankOf(k) - rankOf(ek)), Math.abs(fileOf(k) - fileOf(ek))) === 1)
+      return false;
+  }
+  return attacks(pos, 1 - col, k);
+}
+
+function promotionTypes(pos) {
+  if (pos.variant !== "eightpiece") return [[QUEEN, 0], [ROOK, 0], [BISHOP, 0], [KNIGHT, 0]];
+  const out = [[QUEEN, 0], [ROOK, 0], [BISHOP, 0], [KNIGHT, 0], [SENTRY, 0], [JAILER, 0]];
+  for (let d = 0; d < 8; d++) out.push([LANCER, d]);
+  return out;
+}
+
+// returns the squares a piece of type ( and lancer direction ) standing on s would move to for col,
+// own tells for every square whether it counts as occupied by col, other whether by the opponent
+function pieceTargets(type, dir, col, s, own, other, atomic) {
+  const out = [];
+  const r = rankOf(s);
+  const f = fileOf(s);
+  const slide = (dirs, capture) => {
+    for (const [dr, df] of dirs) {
+      let rr = r + dr;
+      let ff = f + df;
+      while (onBoard(rr, ff)) {
+        const t = sq(rr, ff);
+        if (own[t]) break;
+        if (other[t]) {
+          if (capture) out.push(t);
+          break;
+        }
+        out.push(t);
+        rr += dr;
+        ff += df;
+      }
+    }
+  };
+  const jump = (jumps, capture) => {
+    for (const [dr, df] of jumps) {
+      if (!onBoard(r + dr, f + df)) continue;
+      const t = sq(r + dr, f + df);
+      if (own[t] || (other[t] && !capture)) continue;
+      out.push(t);
+    }
+  };
+  switch (type) {
+    case KNIGHT:
+      jump(KNIGHT_JUMPS, true);
+      break;
+    case KING:
+      jump(QUEEN_DIRS, !atomic);
+      break;
+    case BISHOP:
+      slide(BISHOP_DIRS, true);
+      break;
+    case ROOK:
+      slide(ROOK_DIRS, true);
+      break;
+    case QUEEN:
+      slide(QUEEN_DIRS, true);
+      break;
+    case JAILER:
+      slide(ROOK_DIRS, false);
+      break;
+    case SENTRY:
+      slide(BISHOP_DIRS, false);
+      break;
+    case LANCER: {
+      const [dr, df] = LANCER_DIRS[dir];
+      let rr = r + dr;
+      let ff = f + df;
+      while (onBoard(rr, ff)) {
+        const t = sq(rr, ff);
+        if (other[t]) {
+          out.push(t);
+          break;
+        }
+        if (!own[t]) out.push(t);
+        rr += dr;
+        ff += df;
+      }
+      break;
+    }
+  }
+  return out;
+}
+
+// returns the squares the piece on t can be pushed to by the sentry of col moving from s to t,
+// the pushed piece moves as if it belonged to col
+function pushDestinations(pos, s, t, col) {
+  const b = pos.board;
+  const pushed = b[t];
+  const own = new Array(64).fill(false);
+  const other = new Array(64).fill(false);
+  for (let i = 0; i < 64; i++) {
+    if (!b[i]) continue;
+    if (colorOf(b[i]) === col) own[i] = true;
+    else other[i] = true;
+  }
+  own[s] = false;
+  own[t] = true;
+  other[t] = false;
+  let dests = [];
+  const type = typeOf(pushed);
+  if (type === PAWN) {
+    const fwd = col === WHITE ? 1 : -1;
+    const r = rankOf(t);
+    const f = fileOf(t);
+    if (onBoard(r + fwd, f) && !own[sq(r + fwd, f)] && !other[sq(r + fwd, f)]) {
+      dests.push(sq(r + fwd, f));
+      const start = col === WHITE ? 1 : 6;
+      if (r === start && !own[sq(r + 2 * fwd, f)] && !other[sq(r + 2 * fwd, f)]) dests.push(sq(r + 2 * fwd, f));
+    }
+    for (const df of [-1, 1]) {
+      if (onBoard(r + fwd, f + df) && other[sq(r + fwd, f + df)]) dests.push(sq(r + fwd, f + df));
+    }
+    dests = dests.filter((d) => rankOf(d) !== 0 && rankOf(d) !== 7);
+  } else if (type !== KING) {
+    dests = pieceTargets(type, dirOf(pushed), col, t, own, other, false);
+  }
+  return dests.filter((d) => !b[d] || typeOf(b[d]) !== KING);
+}
+
+const CASTLING = {
+  [WHITE]: [
+    { right: "K", king: sq(0, 4), kingTo: sq(0, 6), partner: sq(0, 7), partnerTo: sq(0, 5), empty: [5, 6], safe: [4, 5, 6] },
+    { right: "Q", king: sq(0, 4), kingTo: sq(0, 2), partner: sq(0, 0), partnerTo: sq(0, 3), empty: [1, 2, 3], safe: [2, 3, 4] },
+  ],
+  [BLACK]: [
+    { right: "k", king: sq(7, 4), kingTo: sq(7, 6), partner: sq(7, 7), partnerTo: sq(7, 5), empty: [61, 62], safe: [60, 61, 62] },
+    { right: "q", king: sq(7, 4), kingTo: sq(7, 2), partner: sq(7, 0), partnerTo: sq(7, 3), empty: [57, 58, 59], safe: [58, 59, 60] },
+  ],
+};
+
+const isPartner = (p, col) => p && colorOf(p) === col && (typeOf(p) === ROOK || typeOf(p) === JAILER);
+
+// generates pseudo legal moves as { from, to, promo, dir, push, castle }
+function generateMoves(pos) {
+  const b = pos.board;
+  const col = pos.turn;
+  const moves = [];
+  const jail = jailed(pos, col);
+  const own = b.map((p) => p !== 0 && colorOf(p) === col);
+  const other = b.map((p) => p !== 0 && colorOf(p) !== col);
+  const atomic = pos.variant === "atomic";
+  for (let s = 0; s < 64; s++) {
+    const p = b[s];
+    if (!p || colorOf(p) !== col || jail[s]) continue;
+    const type = typeOf(p);
+    if (type === PAWN) {
+      const fwd = col === WHITE ? 1 : -1;
+      const r = rankOf(s);
+      const f = fileOf(s);
+      const targets = [];
+      if (onBoard(r + fwd, f) && !b[sq(r + fwd, f)]) {
+        targets.push(sq(r + fwd, f));
+        const start = col === WHITE ? 1 : 6;
+        if (r === start && !b[sq(r + 2 * fwd, f)]) targets.push(sq(r + 2 * fwd, f));
+      }
+      for (const df of [-1, 1]) {
+        if (!onBoard(r + fwd, f + df)) continue;
+        const t = sq(r + fwd, f + df);
+        if (other[t] || (t === pos.ep && !b[t])) targets.push(t);
+      }
+      for (const t of targets) {
+        if (rankOf(t) === 0 || rankOf(t) === 7) {
+          for (const [promo, dir] of promotionTypes(pos)) moves.push({ from: s, to: t, promo, dir });
+        } else {
+          moves.push({ from: s, to: t });
+        }
+      }
+    } else if (type === SENTRY) {
+      for (const t of pieceTargets(SENTRY, 0, col, s, own, other, false)) moves.push({ from: s, to: t });
+      for (const [dr, df] of BISHOP_DIRS) {
+        let rr = rankOf(s) + dr;
+        let ff = fileOf(s) + df;
+        while (onBoard(rr, ff) && !b[sq(rr, ff)]) {
+          rr += dr;
+          ff += df;
+        }
+        if (!onBoard(rr, ff)) continue;
+        const t = sq(rr, ff);
+        if (!other[t] || typeOf(b[t]) === KING) continue;
+        for (const d of pushDestinations(pos, s, t, col)) moves.push({ from: s, to: t, push: d });
+      }
+    } else {
+      for (const t of pieceTargets(type, dirOf(p), col, s, own, other, atomic)) {
+        if (type === LANCER) {
+          for (let d = 0; d < 8; d++) moves.push({ from: s, to: t, promo: LANCER, dir: d });
+        } else {
+          moves.push({ from: s, to: t });
+        }
+      }
+      if (type === KING) {
+        for (const ci of CASTLING[col]) {
+          if (s !== ci.king || !pos.castle[ci.right] || !isPartner(b[ci.partner], col)) continue;
+          if (ci.empty.some((e) => b[e])) continue;
+          if (ci.safe.some((e) => attacks(pos, 1 - col, e))) continue;
+          moves.push({ from: s, to: ci.kingTo, castle: ci });
+        }
+      }
+    }
+  }
+  if (pos.disabled) {
+    const [df, dt] = pos.disabled;
+    return moves.filter((m) => m.from !== df || m.to !== dt);
+  }
+  return moves;
+}
+
+function makeMove(pos, m) {
+  const b = pos.board.slice();
+  const col = pos.turn;
+  const next = {
+    variant: pos.variant,
+    board: b,
+    turn: 1 - col,
+    castle: Object.assign({}, pos.castle),
+    ep: -1,
+    disabled: null,
+  };
+  const p = b[m.from];
+  if (m.castle) {
+    const partner = b[m.castle.partner];
+    b[m.from] = 0;
+    b[m.castle.partner] = 0;
+    b[m.castle.kingTo] = p;
+    b[m.castle.partnerTo] = partner;
+  } else if (m.push !== undefined) {
+    const pushed = b[m.to];
+    b[m.from] = 0;
+    b[m.push] = pushed;
+    b[m.to] = p;
+    next.disabled = [m.push, m.to];
+  } else {
+    let captureSq = m.to;
+    if (typeOf(p) === PAWN && m.to === pos.ep && !b[m.to]) captureSq = sq(rankOf(m.from), fileOf(m.to));
+    const captured = b[captureSq];
+    b[m.from] = 0;
+    b[captureSq] = 0;
+    b[m.to] = m.promo ? mkPiece(m.promo, col, m.dir) : p;
+    if (typeOf(p) === PAWN && Math.abs(rankOf(m.to) - rankOf(m.from)) === 2) {
+      next.ep = sq((rankOf(m.to) + rankOf(m.from)) / 2, fileOf(m.from));
+    }
+    if (captured && pos.variant === "atomic") {
+      b[m.to] = 0;
+      for (const [dr, df] of QUEEN_DIRS) {
+        const r = rankOf(m.to) + dr;
+        const f = fileOf(m.to) + df;
+        if (!onBoard(r, f)) continue;
+        const q = b[sq(r, f)];
+        if (q && typeOf(q) !== PAWN) b[sq(r, f)] = 0;
+      }
+    }
+  }
+  for (const c of [WHITE, BLACK]) {
+    for (const ci of CASTLING[c]) {
+      if (b[ci.king] !== mkPiece(KING, c) || !isPartner(b[ci.partner], c)) next.castle[ci.right] = false;
+    }
+  }
+  return next;
+}
+
+function isLegal(next, mover) {
+  if (next.variant === "atomic") {
+    if (kingSquare(next, mover) < 0) return false;
+    if (kingSquare(next, 1 - mover) < 0) return true;
+  }
+  return !inCheck(next, mover);
+}
+
+function perft(pos, depth) {
+  let nodes = 0;
+  for (const m of generateMoves(pos)) {
+    const next = makeMove(pos, m);
+    if (!isLegal(next, pos.turn)) continue;
+    nodes += depth === 1 ? 1 : perft(next, depth - 1);
+  }
+  return nodes;
+}
+
+function main() {
+  const variant = process.argv[2];
+  const maxNodes = Number(process.argv[3] || 1e7);
+  const lines = require("fs").readFileSync(0, "utf8").split("\n");
+  let failed = 0;
+  for (const raw of lines) {
+    const line = raw.split("#")[0].trim();
+    if (!line || line.startsWith("variant ")) continue;
+    const fields = line.split(";");
+    const fen = fields[0].trim();
+    const pos = parseFen(variant, fen);
+    for (const field of fields.slice(1)) {
+      const [d, n] = field.trim().split(/\s+/);
+      const depth = Number(d.slice(1));
+      const expected = Number(n);
+      if (expected > maxNodes) continue;
+      const got = perft(pos, depth);
+      if (got === expected) {
+        console.log("ok", fen, d, got);
+      } else {
+        failed++;
+        console.log("FAILED", fen, d, "expected", expected, "got", got);
+      }
+    }
+  }
+  process.exit(failed ? 1 : 0);
+}
+
+main();
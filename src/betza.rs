@@ -0,0 +1,402 @@
+use crate::bitboard::*;
+use crate::constants::*;
+use crate::piece::*;
+use crate::square::*;
+
+use once_cell::sync::Lazy;
+use std::sync::{RwLock, RwLockReadGuard};
+
+/// BetzaComponent records one atom of a Betza piece description together with its modifiers
+#[derive(Clone)]
+pub struct BetzaComponent {
+    pub deltas: [Vec<Delta>; 2],
+    pub rider: bool,
+    pub can_move: bool,
+    pub can_capture: bool,
+}
+
+/// BetzaPiece records a custom piece compiled from Betza notation
+///
+/// once registered the piece plays as one of the custom figures, with its fen symbol and value
+#[derive(Clone)]
+pub struct BetzaPiece {
+    pub name: String,
+    pub notation: String,
+    /// symbol records the lower case fen symbol of the piece, upper case for white
+    pub symbol: char,
+    /// value records the material value of the piece in centipawns
+    pub value: Score,
+    pub components: Vec<BetzaComponent>,
+    /// leaper_move records the non capturing leaps of the piece for color and square
+    pub leaper_move: [AttackTable; 2],
+    /// leaper_capture records the capturing leaps of the piece for color and square
+    pub leaper_capture: [AttackTable; 2],
+    /// rider_move records the non capturing rider deltas of the piece for color
+    pub rider_move: [Vec<Delta>; 2],
+    /// rider_capture records the capturing rider deltas of the piece for color
+    pub rider_capture: [Vec<Delta>; 2],
+}
+
+/// returns the rank and file offset of a delta
+pub fn delta_rank_file(delta: &Delta) -> (isize, isize) {
+    match delta {
+        Delta::N => (1, 0),
+        Delta::NE => (1, 1),
+        Delta::NNE => (2, 1),
+        Delta::NEE => (1, 2),
+        Delta::E => (0, 1),
+        Delta::SE => (-1, 1),
+        Delta::SEE => (-1, 2),
+        Delta::SSE => (-2, 1),
+        Delta::S => (-1, 0),
+        Delta::SW => (-1, -1),
+        Delta::SSW => (-2, -1),
+        Delta::SWW => (-1, -2),
+        Delta::W => (0, -1),
+        Delta::NW => (1, -1),
+        Delta::NWW => (1, -2),
+        Delta::NNW => (2, -1),
+    }
+}
+
+/// returns the deltas of a Betza atom and whether the atom is a rider by itself
+fn atom_deltas(atom: char) -> Result<(Vec<Vec<Delta>>, bool), String> {
+    match atom {
+        'W' => Ok((vec![ROOK_DELTAS.to_vec()], false)),
+        'F' => Ok((vec![BISHOP_DELTAS.to_vec()], false)),
+        'N' => Ok((vec![KNIGHT_DELTAS.to_vec()], false)),
+        'K' => Ok((vec![ROOK_DELTAS.to_vec(), BISHOP_DELTAS.to_vec()], false)),
+        'R' => Ok((vec![ROOK_DELTAS.to_vec()], true)),
+        'B' => Ok((vec![BISHOP_DELTAS.to_vec()], true)),
+        'Q' => Ok((vec![ROOK_DELTAS.to_vec(), BISHOP_DELTAS.to_vec()], true)),
+        _ => Err(format!("unsupported betza atom {:?}", atom)),
+    }
+}
+
+/// tells whether a delta ( seen from white ) matches a set of directional modifiers
+///
+/// single modifiers are united, a vertical modifier directly followed by a horizontal one
+/// ( like 'fr' ) selects the deltas that go in both directions
+fn delta_matches_directions(delta: &Delta, directions: &str) -> bool {
+    if directions.is_empty() {
+        return true;
+    }
+    let (dr, df) = delta_rank_file(delta);
+    let matches = |d: char| match d {
+        'f' => dr > 0,
+        'b' => dr < 0,
+        'l' => df < 0,
+        'r' => df > 0,
+        'v' => dr != 0 && df.abs() < dr.abs(),
+        's' => df != 0 && dr.abs() < df.abs(),
+        _ => false,
+    };
+    let chars: Vec<char> = directions.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if (c == 'f' || c == 'b')
+            && i + 1 < chars.len()
+            && (chars[i + 1] == 'l' || chars[i + 1] == 'r')
+        {
+            if matches(c) && matches(chars[i + 1]) {
+                return true;
+            }
+            i += 2;
+        } else {
+            if matches(c) {
+                return true;
+            }
+            i += 1;
+        }
+    }
+    false
+}
+
+/// returns the delta as seen from black, both rank and file are mirrored, as forward and left of black
+/// are backward and right of white
+fn mirror_delta(delta: &Delta) -> Delta {
+    match delta {
+        Delta::N => Delta::S,
+        Delta::NE => Delta::SW,
+        Delta::NNE => Delta::SSW,
+        Delta::NEE => Delta::SWW,
+        Delta::E => Delta::W,
+        Delta::SE => Delta::NW,
+        Delta::SEE => Delta::NWW,
+        Delta::SSE => Delta::NNW,
+        Delta::S => Delta::N,
+        Delta::SW => Delta::NE,
+        Delta::SSW => Delta::NNE,
+        Delta::SWW => Delta::NEE,
+        Delta::W => Delta::E,
+        Delta::NW => Delta::SE,
+        Delta::NWW => Delta::SEE,
+        Delta::NNW => Delta::SSE,
+    }
+}
+
+/// parses a Betza notation into components
+///
+/// supported atoms are W, F, N and the shorthands K, R, B, Q, a doubled atom or an atom followed by 0
+/// makes a rider ( WW, NN, F0 ), supported modifiers are m ( move only ), c ( capture only ) and
+/// the directions f, b, l, r, v, s
+pub fn parse_betza(notation: &str) -> Result<Vec<BetzaComponent>, String> {
+    let mut components: Vec<BetzaComponent> = Vec::new();
+    let chars: Vec<char> = notation.chars().collect();
+    let mut i = 0;
+    let mut modifiers = "".to_string();
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        if c.is_ascii_lowercase() {
+            if !"mcfblrvs".contains(c) {
+                return Err(format!("unsupported betza modifier {:?}", c));
+            }
+            modifiers.push(c);
+            continue;
+        }
+        let (delta_groups, mut rider) = atom_deltas(c)?;
+        if i < chars.len() && (chars[i] == c || chars[i] == '0') {
+            rider = true;
+            i += 1;
+        }
+        let mut can_move = !modifiers.contains('c');
+        let mut can_capture = !modifiers.contains('m');
+        if !can_move && !can_capture {
+            can_move = true;
+            can_capture = true;
+        }
+        let directions: String = modifiers
+            .chars()
+            .filter(|m| *m != 'm' && *m != 'c')
+            .collect();
+        let mut white_deltas: Vec<Delta> = Vec::new();
+        for group in delta_groups.iter() {
+            for delta in group.iter() {
+                if delta_matches_directions(delta, &directions) {
+                    white_deltas.push(*delta);
+                }
+            }
+        }
+        if white_deltas.is_empty() {
+            return Err(format!(
+                "betza modifiers {:?} select no direction for {:?}",
+                directions, c
+            ));
+        }
+        let black_deltas: Vec<Delta> = white_deltas.iter().map(mirror_delta).collect();
+        let mut deltas = [Vec::new(), Vec::new()];
+        deltas[BLACK] = black_deltas;
+        deltas[WHITE] = white_deltas;
+        components.push(BetzaComponent {
+            deltas,
+            rider,
+            can_move,
+            can_capture,
+        });
+        modifiers = "".to_string();
+    }
+    if !modifiers.is_empty() {
+        return Err(format!("betza modifiers {:?} without atom", modifiers));
+    }
+    if components.is_empty() {
+        return Err("empty betza notation".to_string());
+    }
+    Ok(components)
+}
+
+/// BetzaPiece implementation
+impl BetzaPiece {
+    /// compiles a Betza notation into a piece with leaper attack tables and rider deltas
+    pub fn new(name: &str, notation: &str) -> Result<BetzaPiece, String> {
+        let components = parse_betza(notation)?;
+        let mut piece = BetzaPiece {
+            name: name.to_string(),
+            notation: notation.to_string(),
+            symbol: '?',
+            value: 0,
+            components: components.clone(),
+            leaper_move: [EMPTY_ATTACK_TABLE, EMPTY_ATTACK_TABLE],
+            leaper_capture: [EMPTY_ATTACK_TABLE, EMPTY_ATTACK_TABLE],
+            rider_move: [Vec::new(), Vec::new()],
+            rider_capture: [Vec::new(), Vec::new()],
+        };
+        for col in BLACK..WHITE + 1 {
+            for component in components.iter() {
+                let deltas = &component.deltas[col];
+                if component.rider {
+                    if component.can_move {
+                        piece.rider_move[col].extend(deltas.iter());
+                    }
+                    if component.can_capture {
+                        piece.rider_capture[col].extend(deltas.iter());
+                    }
+                } else {
+                    for sq in 0..BOARD_AREA {
+                        let attack = jump_attack(sq, deltas, 0);
+                        if component.can_move {
+                            piece.leaper_move[col][sq] |= attack;
+                        }
+                        if component.can_capture {
+                            piece.leaper_capture[col][sq] |= attack;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(piece)
+    }
+
+    /// returns the squares the piece of color at square attacks given an occupancy
+    pub fn attack(&self, sq: Square, col: Color, occup: Bitboard) -> Bitboard {
+        self.leaper_capture[col][sq] | sliding_attack(sq, &self.rider_capture[col], occup)
+    }
+
+    /// returns mobility of the piece of color at square
+    pub fn mobility(
+        &self,
+        sq: Square,
+        gen_mode: MoveGenMode,
+        col: Color,
+        occup_us: Bitboard,
+        occup_them: Bitboard,
+    ) -> Bitboard {
+        let occup = occup_us | occup_them;
+        let quiet =
+            (self.leaper_move[col][sq] | sliding_attack(sq, &self.rider_move[col], occup)) & !occup;
+        let violent = self.attack(sq, col, occup) & occup_them;
+        match gen_mode {
+            MoveGenMode::All => quiet | violent,
+            MoveGenMode::Violent => violent,
+            MoveGenMode::Quiet => quiet,
+        }
+    }
+
+    /// returns the piece as pretty printable string, showing its mobility from a square on an empty board
+    pub fn pretty_print_string(&self, sq: Square) -> String {
+        format!(
+            "{} {} ( {} ) value {} from {}\n{}",
+            self.name,
+            self.symbol,
+            self.notation,
+            self.value,
+            sq.uci(),
+            self.mobility(sq, MoveGenMode::All, WHITE, sq.bitboard(), 0)
+                .pretty_print_string()
+        )
+    }
+}
+
+/// parses custom piece definitions, one "name symbol notation value" line per piece, '#' starts a comment
+pub fn parse_betza_pieces(content: &str) -> Result<Vec<BetzaPiece>, String> {
+    let mut pieces: Vec<BetzaPiece> = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() != 4 {
+            return Err(format!(
+                "line {} : expected name, symbol, notation and value",
+                i + 1
+            ));
+        }
+        let mut piece = BetzaPiece::new(parts[0], parts[2])
+            .map_err(|err| format!("line {} : {}", i + 1, err))?;
+        let mut symbol = parts[1].chars();
+        match (symbol.next(), symbol.next()) {
+            (Some(c), None) if c.is_ascii_alphabetic() => piece.symbol = c.to_ascii_lowercase(),
+            _ => return Err(format!("line {} : invalid symbol {:?}", i + 1, parts[1])),
+        }
+        piece.value = parts[3]
+            .parse()
+            .map_err(|_| format!("line {} : invalid value {:?}", i + 1, parts[3]))?;
+        pieces.push(piece);
+    }
+    Ok(pieces)
+}
+
+/// loads custom piece definitions from a config file
+pub fn load_betza_pieces(path: &str) -> Result<Vec<BetzaPiece>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| format!("could not read {} : {}", path, err))?;
+    parse_betza_pieces(&content)
+}
+
+/// CUSTOM_PIECES records the registered custom pieces, the piece at index i plays as figure CUSTOM_MIN + i
+static CUSTOM_PIECES: Lazy<RwLock<Vec<BetzaPiece>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// returns the registered custom pieces for reading
+fn read_custom_pieces() -> RwLockReadGuard<'static, Vec<BetzaPiece>> {
+    CUSTOM_PIECES
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// registers custom pieces, replacing the ones registered before, the pieces get the custom figures in order
+///
+/// positions holding previously registered custom pieces should not be used afterwards
+pub fn register_custom_pieces(pieces: &[BetzaPiece]) -> Result<(), String> {
+    if pieces.len() > NUM_CUSTOM_FIGURES {
+        return Err(format!(
+            "too many custom pieces {} , at most {} allowed",
+            pieces.len(),
+            NUM_CUSTOM_FIGURES
+        ));
+    }
+    for (i, piece) in pieces.iter().enumerate() {
+        if !piece.symbol.is_ascii_lowercase() || RESERVED_FEN_SYMBOLS.contains(piece.symbol) {
+            return Err(format!(
+                "invalid symbol {:?} for {}",
+                piece.symbol, piece.name
+            ));
+        }
+        if pieces[..i].iter().any(|other| other.symbol == piece.symbol) {
+            return Err(format!(
+                "duplicate symbol {:?} for {}",
+                piece.symbol, piece.name
+            ));
+        }
+    }
+    let mut registered = CUSTOM_PIECES
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    for fig in CUSTOM_MIN..CUSTOM_MAX + 1 {
+        match pieces.get(fig - CUSTOM_MIN) {
+            Some(piece) => set_custom_figure(fig, piece.symbol as u8, piece.value),
+            None => set_custom_figure(fig, 0, 0),
+        }
+    }
+    *registered = pieces.to_vec();
+    Ok(())
+}
+
+/// returns the registered custom pieces
+pub fn custom_pieces() -> Vec<BetzaPiece> {
+    read_custom_pieces().clone()
+}
+
+/// returns the squares the custom figure of color at square attacks given an occupancy
+pub fn custom_figure_attack(fig: Figure, sq: Square, col: Color, occup: Bitboard) -> Bitboard {
+    match read_custom_pieces().get(fig - CUSTOM_MIN) {
+        Some(piece) => piece.attack(sq, col, occup),
+        None => 0,
+    }
+}
+
+/// returns mobility of the custom figure of color at square
+pub fn custom_figure_mobility(
+    fig: Figure,
+    sq: Square,
+    gen_mode: MoveGenMode,
+    col: Color,
+    occup_us: Bitboard,
+    occup_them: Bitboard,
+) -> Bitboard {
+    match read_custom_pieces().get(fig - CUSTOM_MIN) {
+        Some(piece) => piece.mobility(sq, gen_mode, col, occup_us, occup_them),
+        None => 0,
+    }
+}
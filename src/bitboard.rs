@@ -37,8 +37,8 @@ impl BitboardTrait for Bitboard {
             if bits % 8 == 7 {
                 buff += "*\n"
             }
-            bb = bb << 1;
-            bits = bits + 1;
+            bb <<= 1;
+            bits += 1;
             if bits == 64 {
                 break;
             }
@@ -57,7 +57,7 @@ impl BitboardTrait for Bitboard {
 
         *self &= !bb;
 
-        return (bb, true);
+        (bb, true)
     }
 
     /// pops a square from the bitboard and returns it
@@ -0,0 +1,147 @@
+use crate::constants::*;
+use crate::lineargame::*;
+use crate::piece::*;
+use crate::square::*;
+
+/// NUM_BUGHOUSE_BOARDS tells the number of boards of a bughouse game
+pub const NUM_BUGHOUSE_BOARDS: usize = 2;
+
+/// BughouseMove records a move made on one of the boards of a bughouse game
+#[derive(Copy, Clone)]
+pub struct BughouseMove {
+    pub board: usize,
+    pub mv: Move,
+    pub captured: Piece,
+}
+
+/// BughouseGame represents a two board bughouse game, captures on one board feed the pocket of the other board
+pub struct BughouseGame {
+    pub boards: [LinearGame; NUM_BUGHOUSE_BOARDS],
+    pub history: Vec<BughouseMove>,
+}
+
+/// BughouseGame defaults to a new bughouse game at the start position
+impl Default for BughouseGame {
+    fn default() -> BughouseGame {
+        BughouseGame::new()
+    }
+}
+
+/// BughouseGame implementation
+impl BughouseGame {
+    /// returns a new bughouse game initialized to the start position on both boards
+    pub fn new() -> BughouseGame {
+        let mut game = BughouseGame {
+            boards: [LinearGame::new(), LinearGame::new()],
+            history: Vec::new(),
+        };
+        game.init();
+        game
+    }
+
+    /// initializes both boards to the start position
+    pub fn init(&mut self) {
+        for board in self.boards.iter_mut() {
+            board.init(VARIANT_BUGHOUSE);
+        }
+        self.history = Vec::new();
+    }
+
+    /// sets the game from a bfen, the fens of the two boards separated by '|'
+    ///
+    /// the game is left unchanged if the bfen is invalid
+    pub fn set_from_bfen(&mut self, bfen: &str) -> Result<(), String> {
+        let fens: Vec<&str> = bfen.split('|').map(|fen| fen.trim()).collect();
+        if fens.len() != NUM_BUGHOUSE_BOARDS {
+            return Err(format!("invalid number of bughouse boards {}", fens.len()));
+        }
+        let mut boards = [LinearGame::new(), LinearGame::new()];
+        for (board, fen) in boards.iter_mut().zip(fens.iter()) {
+            board.init_from_fen(VARIANT_BUGHOUSE, fen)?;
+        }
+        self.boards = boards;
+        self.history = Vec::new();
+        Ok(())
+    }
+
+    /// reports the game as bfen
+    pub fn report_bfen(&mut self) -> String {
+        format!(
+            "{} | {}",
+            self.boards[0].current().report_fen(),
+            self.boards[1].current().report_fen()
+        )
+    }
+
+    /// returns the index of the partner board of a board
+    pub fn partner(board: usize) -> usize {
+        1 - board
+    }
+
+    /// pushes a move on a board, a captured piece goes to the pocket of the partner board
+    pub fn push(&mut self, board: usize, mv: Move) -> Result<(), String> {
+        let state = self.boards[board].current();
+        let mut captured = state.captured_piece(mv);
+        if captured != NO_PIECE && state.is_promoted(state.capture_square(mv)) {
+            captured = color_figure(captured.color(), PAWN);
+        }
+        self.boards[board].push(mv)?;
+        if captured != NO_PIECE {
+            self.boards[BughouseGame::partner(board)]
+                .current()
+                .add_to_pocket(captured);
+        }
+        self.history.push(BughouseMove {
+            board,
+            mv,
+            captured,
+        });
+        Ok(())
+    }
+
+    /// pops the last move made on either board
+    pub fn pop(&mut self) {
+        if let Some(bmv) = self.history.pop() {
+            if bmv.captured != NO_PIECE {
+                self.boards[BughouseGame::partner(bmv.board)]
+                    .current()
+                    .remove_from_pocket(bmv.captured);
+            }
+            self.boards[bmv.board].pop();
+        }
+    }
+
+    /// pushes a move by index in the move buff of a board
+    pub fn push_by_index(&mut self, board: usize, index: usize) -> Result<(), String> {
+        if board >= NUM_BUGHOUSE_BOARDS || index >= self.boards[board].current().move_buff.len() {
+            return Err(format!("invalid move index {}", index + 1));
+        }
+        let mv = self.boards[board].current().move_buff[index].mv;
+        self.push(board, mv)
+    }
+
+    /// returns the joint history of both boards in uci notation
+    pub fn history_uci(&self) -> String {
+        self.history
+            .iter()
+            .map(|bmv| format!("{}{}", if bmv.board == 0 { "A" } else { "B" }, bmv.mv.uci()))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// returns the game as pretty printable string
+    pub fn pretty_print_string(&mut self) -> String {
+        format!(
+            "board A\n\n{}\nboard B\n\n{}\nbfen {}\nhistory {}\n",
+            self.boards[0].pretty_print_string(),
+            self.boards[1].pretty_print_string(),
+            self.report_bfen(),
+            self.history_uci()
+        )
+    }
+
+    /// prints the game
+    pub fn print(&mut self) {
+        println!("{}", self.pretty_print_string())
+    }
+}
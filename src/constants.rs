@@ -30,13 +30,68 @@ pub const LANCER_DELTAS: [Delta; NUM_LANCERS] = [
 pub const SQUARE_SIZE_IN_BITS: usize = 6;
 
 /// SQUARE_MASK can be used to mask the bits representing a square
-pub const SQUARE_MASK: u32 = (1 << SQUARE_SIZE_IN_BITS) - 1;
+pub const SQUARE_MASK: u64 = (1 << SQUARE_SIZE_IN_BITS) - 1;
 
 /// FROM_SQ_SHIFT is the shift of from square in Move
 pub const FROM_SQ_SHIFT: usize = 0;
 /// TO_SQ_SHIFT is the shift of to square in Move
 pub const TO_SQ_SHIFT: usize = SQUARE_SIZE_IN_BITS;
 
+/// FIGURE_SIZE_IN_BITS tells the number of bits used to represent a figure
+pub const FIGURE_SIZE_IN_BITS: usize = 5;
+
+/// FIGURE_MASK can be used to mask the bits representing a figure
+pub const FIGURE_MASK: u64 = (1 << FIGURE_SIZE_IN_BITS) - 1;
+
+/// DROP_FIGURE_SHIFT is the shift of the dropped figure in Move, the dropped figure is NO_FIGURE for non drop moves
+pub const DROP_FIGURE_SHIFT: usize = 2 * SQUARE_SIZE_IN_BITS;
+/// PROMOTION_FIGURE_SHIFT is the shift of the promotion figure in Move, also used for the new direction of a lancer
+pub const PROMOTION_FIGURE_SHIFT: usize = DROP_FIGURE_SHIFT + FIGURE_SIZE_IN_BITS;
+/// PUSH_SQ_SHIFT is the shift of the square where a sentry push moves the pushed piece
+pub const PUSH_SQ_SHIFT: usize = PROMOTION_FIGURE_SHIFT + FIGURE_SIZE_IN_BITS;
+
+/// PIECE_SIZE_IN_BITS tells the number of bits used to represent a piece
+pub const PIECE_SIZE_IN_BITS: usize = FIGURE_SIZE_IN_BITS + 1;
+
+/// PIECE_MASK can be used to mask the bits representing a piece
+pub const PIECE_MASK: u64 = (1 << PIECE_SIZE_IN_BITS) - 1;
+
+/// MOVING_PIECE_SHIFT is the shift of the moving piece in Move, the dropped piece for drops
+pub const MOVING_PIECE_SHIFT: usize = PUSH_SQ_SHIFT + SQUARE_SIZE_IN_BITS;
+/// CAPTURED_PIECE_SHIFT is the shift of the captured piece in Move, NO_PIECE for non captures
+pub const CAPTURED_PIECE_SHIFT: usize = MOVING_PIECE_SHIFT + PIECE_SIZE_IN_BITS;
+/// MOVE_KIND_SHIFT is the shift of the kind of the move in Move
+pub const MOVE_KIND_SHIFT: usize = CAPTURED_PIECE_SHIFT + PIECE_SIZE_IN_BITS;
+
+/// MOVE_KIND_SIZE_IN_BITS tells the number of bits used to represent a move kind
+pub const MOVE_KIND_SIZE_IN_BITS: usize = 3;
+
+/// MOVE_KIND_MASK can be used to mask the bits representing a move kind
+pub const MOVE_KIND_MASK: u64 = (1 << MOVE_KIND_SIZE_IN_BITS) - 1;
+
+/// MOVE_KIND_NORMAL represents a normal move or capture, including lancer moves
+pub const MOVE_KIND_NORMAL: MoveKind = 0;
+/// MOVE_KIND_CASTLE represents castling
+pub const MOVE_KIND_CASTLE: MoveKind = 1;
+/// MOVE_KIND_EN_PASSANT represents an en passant capture
+pub const MOVE_KIND_EN_PASSANT: MoveKind = 2;
+/// MOVE_KIND_PROMOTION represents a pawn promotion, with or without capture
+pub const MOVE_KIND_PROMOTION: MoveKind = 3;
+/// MOVE_KIND_SENTRY_PUSH represents a sentry pushing an enemy piece
+pub const MOVE_KIND_SENTRY_PUSH: MoveKind = 4;
+/// MOVE_KIND_DROP represents dropping a piece from the pocket
+pub const MOVE_KIND_DROP: MoveKind = 5;
+
+/// MOVE_KIND_NAMES lists the names of the move kinds
+pub const MOVE_KIND_NAMES: [&str; 6] = [
+    "normal",
+    "castle",
+    "en passant",
+    "promotion",
+    "sentry push",
+    "drop",
+];
+
 /// EMPTY_CASTLING_RIGHT represents an empty castling right
 pub const EMPTY_CASTLING_RIGHT: CastlingRight = CastlingRight { can_castle: false };
 
@@ -45,13 +100,61 @@ pub const EMPTY_COLOR_CASTLING_RIGHTS: ColorCastlingRights = ColorCastlingRights
     rights: [EMPTY_CASTLING_RIGHT, EMPTY_CASTLING_RIGHT],
 };
 
+/// CASTLING_KING_SQUARES tells the start square of the king for color
+pub const CASTLING_KING_SQUARES: [Square; 2] = [SQUARE_E8, SQUARE_E1];
+
+/// CASTLING_INFOS records castling squares for color and side
+pub const CASTLING_INFOS: [[CastlingInfo; 2]; 2] = [
+    [
+        CastlingInfo {
+            king_to_sq: SQUARE_G8,
+            partner_from_sq: SQUARE_H8,
+            partner_to_sq: SQUARE_F8,
+            empty: 0x0600000000000000,
+            safe: 0x0e00000000000000,
+        },
+        CastlingInfo {
+            king_to_sq: SQUARE_C8,
+            partner_from_sq: SQUARE_A8,
+            partner_to_sq: SQUARE_D8,
+            empty: 0x7000000000000000,
+            safe: 0x3800000000000000,
+        },
+    ],
+    [
+        CastlingInfo {
+            king_to_sq: SQUARE_G1,
+            partner_from_sq: SQUARE_H1,
+            partner_to_sq: SQUARE_F1,
+            empty: 0x0000000000000006,
+            safe: 0x000000000000000e,
+        },
+        CastlingInfo {
+            king_to_sq: SQUARE_C1,
+            partner_from_sq: SQUARE_A1,
+            partner_to_sq: SQUARE_D1,
+            empty: 0x0000000000000070,
+            safe: 0x0000000000000038,
+        },
+    ],
+];
+
+/// FIFTY_MOVE_RULE_PLIES tells the number of plies without capture or pawn move that draw the game
+pub const FIFTY_MOVE_RULE_PLIES: usize = 100;
+
+/// SEARCH_REPETITION_COUNT tells the number of occurences of a position that the search scores as draw
+pub const SEARCH_REPETITION_COUNT: usize = 2;
+
+/// GAME_REPETITION_COUNT tells the number of occurences of a position that draw the game
+pub const GAME_REPETITION_COUNT: usize = 3;
+
 /// KING_SIDE is the index for king side castling right
 pub const KING_SIDE: usize = 0;
 /// QUEEN_SIDE is the index for queen side castling right
 pub const QUEEN_SIDE: usize = 1;
 
 /// NUM_VARIANTS tells the number of possible variants
-pub const NUM_VARIANTS: usize = 3;
+pub const NUM_VARIANTS: usize = 5;
 
 /// VARIANT_STANDARD is the index for Standard variant
 pub const VARIANT_STANDARD: Variant = 0;
@@ -59,6 +162,10 @@ pub const VARIANT_STANDARD: Variant = 0;
 pub const VARIANT_EIGHTPIECE: Variant = 1;
 /// VARIANT_ATOMIC is the index for Atomic variant
 pub const VARIANT_ATOMIC: Variant = 2;
+/// VARIANT_BUGHOUSE is the index for Bughouse variant
+pub const VARIANT_BUGHOUSE: Variant = 3;
+/// VARIANT_PLACEMENT is the index for Placement variant
+pub const VARIANT_PLACEMENT: Variant = 4;
 
 /// DEFAULT_VARIANT tells the default variant
 pub const DEFAULT_VARIANT: Variant = VARIANT_EIGHTPIECE;
@@ -69,22 +176,86 @@ pub const VARIANT_INFOS: [VariantInfo; NUM_VARIANTS] = [
         // standard
         start_fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
         display_name: "Standard",
+        has_pockets: false,
     },
     VariantInfo {
         // eightpiece
         start_fen: "jlsesqkbnr/pppppppp/8/8/8/8/PPPPPPPP/JLneSQKBNR w KQkq - 0 1 -",
         display_name: "Eightpiece",
+        has_pockets: false,
     },
     VariantInfo {
         // atomic
         start_fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
         display_name: "Atomic",
+        has_pockets: false,
+    },
+    VariantInfo {
+        // bughouse
+        start_fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+        display_name: "Bughouse",
+        has_pockets: true,
+    },
+    VariantInfo {
+        // placement
+        start_fen: "8/pppppppp/8/8/8/8/PPPPPPPP/8[KQRRBBNNkqrrbbnn] w - - 0 1",
+        display_name: "Placement",
+        has_pockets: true,
     },
 ];
 
-/// MAX_STATES tells the maximum number of states in a LinearGaeme
+/// MAX_MOVES tells the capacity of a MoveList, enough for the richest Eightpiece and drop positions,
+/// generating more moves for one position is a bug caught by a debug assertion
+pub const MAX_MOVES: usize = 1024;
+
+/// NUM_KILLERS tells the number of killer moves kept per ply
+pub const NUM_KILLERS: usize = 2;
+
+/// MAX_UNDO_SQUARES tells the maximum number of squares a move can change, an atomic capture changes the most
+pub const MAX_UNDO_SQUARES: usize = 16;
+
+/// MAX_STATES tells the number of states a LinearGame preallocates, the history grows on demand beyond
 pub const MAX_STATES: usize = 100;
 
+/// MAX_PLY tells the maximum search depth in plies from the root, including extensions
+pub const MAX_PLY: usize = 128;
+
+/// INFINITE_SCORE tells a score larger than any search score
+pub const INFINITE_SCORE: Score = 32000;
+
+/// MATE_SCORE tells the score of giving mate at the root, mate in n plies scores MATE_SCORE - n
+pub const MATE_SCORE: Score = 30000;
+
+/// MATE_BOUND tells the smallest absolute score that represents a forced mate
+pub const MATE_BOUND: Score = MATE_SCORE - MAX_PLY as Score;
+
+/// DRAW_SCORE tells the score of a drawn position
+pub const DRAW_SCORE: Score = 0;
+
+/// DEFAULT_HASH_MIB tells the default size of the transposition table in MiB
+pub const DEFAULT_HASH_MIB: usize = 16;
+
+/// MAX_HASH_MIB tells the maximum size of the transposition table in MiB
+pub const MAX_HASH_MIB: usize = 65536;
+
+/// DEFAULT_MOVE_OVERHEAD tells the default time in milliseconds reserved per move for communication lag
+pub const DEFAULT_MOVE_OVERHEAD: u64 = 30;
+
+/// MAX_MOVE_OVERHEAD tells the maximum move overhead in milliseconds
+pub const MAX_MOVE_OVERHEAD: u64 = 5000;
+
+/// MAX_THREADS tells the maximum number of search threads
+pub const MAX_THREADS: usize = 256;
+
+/// MAX_MULTI_PV tells the maximum number of lines of a multipv search
+pub const MAX_MULTI_PV: usize = 256;
+
+/// SEARCH_STACK_SIZE tells the stack size of search threads, every ply keeps its move lists on the stack
+pub const SEARCH_STACK_SIZE: usize = 64 << 20;
+
+/// MAX_GAME_PLIES tells the maximum number of plies a LinearGame history can hold, including search plies
+pub const MAX_GAME_PLIES: usize = 1 << 16;
+
 /// EMPTRY_REP represents and empty chess board
 pub const EMPTY_REP: [Piece; BOARD_AREA] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -92,11 +263,20 @@ pub const EMPTY_REP: [Piece; BOARD_AREA] = [
 ];
 
 /// FIGURE_ARRAY_SIZE tells the number of possible figures
-pub const FIGURE_ARRAY_SIZE: usize = 18;
+pub const FIGURE_ARRAY_SIZE: usize = 22;
 
 /// EMTPY_FIGURE_BITBOARDS represents an empty bitboard for all possible figures
-pub const EMTPY_FIGURE_BITBOARDS: [Bitboard; FIGURE_ARRAY_SIZE] =
-    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+pub const EMTPY_FIGURE_BITBOARDS: [Bitboard; FIGURE_ARRAY_SIZE] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// EMPTY_POCKET represents an empty pocket of a color
+pub const EMPTY_POCKET: [usize; FIGURE_ARRAY_SIZE] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// POCKET_FIGURES lists the figures that can be held in a pocket, in reporting order
+pub const POCKET_FIGURES: [Figure; 6] = [KING, QUEEN, ROOK, BISHOP, KNIGHT, PAWN];
 
 /// BITBOARD_MIDDLE represents the bitboard for the middle of the board
 pub const BITBOARD_MIDDLE: Bitboard = 0x007E7E7E7E7E7E00;
@@ -108,6 +288,12 @@ pub const BITBOARD_RANK_8_MIDDLE: Bitboard = 0x7E00000000000000;
 pub const BITBOARD_RANK_1: Bitboard = 0x00000000000000ff;
 /// BITBOARD_RANK_1_MIDDLE represents the bitboard for the middle of the 1st rank of the board
 pub const BITBOARD_RANK_1_MIDDLE: Bitboard = 0x000000000000007E;
+/// BACK_RANKS tells the first rank for color
+pub const BACK_RANKS: [Bitboard; 2] = [BITBOARD_RANK_8, BITBOARD_RANK_1];
+/// BITBOARD_DARK_SQUARES represents the dark squares of the board
+pub const BITBOARD_DARK_SQUARES: Bitboard = 0x55aa55aa55aa55aa;
+/// BITBOARD_LIGHT_SQUARES represents the light squares of the board
+pub const BITBOARD_LIGHT_SQUARES: Bitboard = 0xaa55aa55aa55aa55;
 /// BITBOARD_FILE_A represents file 'a' of the board
 pub const BITBOARD_FILE_A: Bitboard = 0x8080808080808080;
 /// BITBOARD_FILE_A_MIDDLE represents the middle of file 'a' of the board
@@ -122,11 +308,11 @@ pub const BLACK: Color = 0;
 /// WHITE represents white chess color
 pub const WHITE: Color = 1;
 
-/// PIECE_FEN_SYMBOLS maps a piece to its fen symbol
-pub const PIECE_FEN_SYMBOLS: [&str; 36] = [
+/// PIECE_FEN_SYMBOLS maps a piece to its fen symbol, custom pieces get their symbol when registered
+pub const PIECE_FEN_SYMBOLS: [&str; 44] = [
     ".", ".", "p", "P", "n", "N", "b", "B", "r", "R", "q", "Q", "k", "K", "l", "L", "ln", "Ln",
     "lne", "Lne", "le", "Le", "lse", "Lse", "ls", "Ls", "lsw", "Lsw", "lw", "Lw", "lnw", "Lnw",
-    "s", "S", "j", "J",
+    "s", "S", "j", "J", "?", "?", "?", "?", "?", "?", "?", "?",
 ];
 
 /// NO_FIGURE represents no piece on a given square
@@ -169,25 +355,52 @@ pub const SENTRY: Figure = 16;
 /// JAILER represents chess figure 'jailer'
 pub const JAILER: Figure = 17;
 
+/// NUM_CUSTOM_FIGURES tells the number of figures reserved for custom pieces defined in Betza notation
+pub const NUM_CUSTOM_FIGURES: usize = 4;
+/// CUSTOM_MIN tells the lowest custom figure
+pub const CUSTOM_MIN: Figure = 18;
+/// CUSTOM_MAX tells the highest custom figure
+pub const CUSTOM_MAX: Figure = CUSTOM_MIN + NUM_CUSTOM_FIGURES - 1;
+
+/// RESERVED_FEN_SYMBOLS lists the fen symbols ( lower case ) custom pieces cannot take, 'e' and 'w' are lancer directions
+pub const RESERVED_FEN_SYMBOLS: &str = "pnbrqklsjew";
+
 /// LANCER_MIN tells the lowest lancer
 pub const LANCER_MIN: Figure = LANCERN;
 /// LANCER_MAX tells the highest lancer
 pub const LANCER_MAX: Figure = LANCERNW;
 
+/// PROMOTION_FIGURES lists the figures a pawn can promote to
+pub const PROMOTION_FIGURES: [Figure; 4] = [QUEEN, ROOK, BISHOP, KNIGHT];
+
+/// EIGHTPIECE_PROMOTION_FIGURES lists the figures a pawn can promote to in Eightpiece, lancers with any direction
+pub const EIGHTPIECE_PROMOTION_FIGURES: [Figure; 14] = [
+    QUEEN, ROOK, BISHOP, KNIGHT, SENTRY, JAILER, LANCERN, LANCERNE, LANCERE, LANCERSE, LANCERS,
+    LANCERSW, LANCERW, LANCERNW,
+];
+
 /// FIG_MIN tells the lowest non empty figure
 pub const FIG_MIN: Figure = PAWN;
 /// FIG_MAX tells the highest non empty figure
-pub const FIG_MAX: Figure = JAILER;
+pub const FIG_MAX: Figure = CUSTOM_MAX;
 
-/// FIGURE_FEN_SYMBOLS maps a figure to its fen symbol
-pub const FIGURE_FEN_SYMBOLS: [&str; 18] = [
+/// FIGURE_FEN_SYMBOLS maps a figure to its fen symbol, custom figures get their symbol when registered
+pub const FIGURE_FEN_SYMBOLS: [&str; 22] = [
     ".", "p", "n", "b", "r", "q", "k", "l", "ln", "lne", "le", "lse", "ls", "lsw", "lw", "lnw",
-    "s", "j",
+    "s", "j", "?", "?", "?", "?",
+];
+
+/// FIGURE_SAN_LETTERS maps a figure to its san letter, custom figures get their letter when registered
+pub const FIGURE_SAN_LETTERS: [&str; 22] = [
+    ".", "P", "N", "B", "R", "Q", "K", "L", "L", "L", "L", "L", "L", "L", "L", "L", "S", "J", "?",
+    "?", "?", "?",
 ];
 
-/// FIGURE_SAN_LETTERS maps a figure to its san letter
-pub const FIGURE_SAN_LETTERS: [&str; 18] = [
-    ".", "P", "N", "B", "R", "Q", "K", "L", "L", "L", "L", "L", "L", "L", "L", "L", "S", "J",
+/// FIGURE_VALUES maps a figure to its material value in centipawns, the king has no material value,
+/// custom figures get their value when registered
+pub const FIGURE_VALUES: [Score; 22] = [
+    0, 100, 300, 325, 500, 950, 0, 400, 400, 400, 400, 400, 400, 400, 400, 400, 325, 400, 0, 0, 0,
+    0,
 ];
 
 /// NUM_RANKS tells the number of ranks of a chess board
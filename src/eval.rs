@@ -0,0 +1,90 @@
+use crate::bitboard::*;
+use crate::constants::*;
+use crate::piece::*;
+use crate::square::*;
+use crate::state::*;
+
+/// CENTER_WEIGHTS maps a figure to its bonus per step towards the center, in centipawns
+const CENTER_WEIGHTS: [Score; FIGURE_ARRAY_SIZE] = [
+    0, 0, 4, 2, 1, 1, 0, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+];
+
+/// PAWN_ADVANCE_BONUS tells the bonus of a pawn per rank advanced, in centipawns
+const PAWN_ADVANCE_BONUS: Score = 4;
+
+/// returns the number of steps a square is away from the edge of the board, 0 to 3
+fn center_steps(sq: Square) -> Score {
+    let file = std::cmp::min(sq.file(), LAST_FILE - sq.file());
+    let rank = std::cmp::min(sq.rank(), LAST_RANK - sq.rank());
+    std::cmp::min(file, rank) as Score
+}
+
+/// returns the static evaluation of color, material on the board and in the pocket plus placement
+fn evaluate_color(state: &State, col: Color) -> Score {
+    let mut score: Score = 0;
+    let mut bb = state.color_bitboard(col);
+    loop {
+        let (sq, ok) = bb.pop_square();
+        if !ok {
+            break;
+        }
+        let fig = state.piece_at_square(sq).figure();
+        score += fig.value() + CENTER_WEIGHTS[fig] * center_steps(sq);
+        if fig == PAWN {
+            let advance = if col == WHITE {
+                sq.rank().saturating_sub(RANK_2)
+            } else {
+                RANK_7.saturating_sub(sq.rank())
+            };
+            score += PAWN_ADVANCE_BONUS * advance as Score;
+        }
+    }
+    for fig in POCKET_FIGURES.iter() {
+        score += fig.value() * state.pocket_count(col, *fig) as Score;
+    }
+    score
+}
+
+/// returns the material the side to move wins by a violent move, in atomic the exploded pieces are counted
+/// for both sides, exploding the enemy king gains more than any material
+pub fn capture_gain(state: &State, mv: Move) -> Score {
+    let mut gain = mv.captured_piece().figure().value();
+    if state.variant() != VARIANT_ATOMIC || !mv.is_capture() {
+        if mv.is_promotion() {
+            gain += mv.promotion_figure().value() - PAWN.value();
+        }
+        return gain;
+    }
+    // the capturing piece explodes, promoted or not
+    gain -= mv.moving_piece().figure().value();
+    let to_sq = mv.to_sq();
+    let mut area = KING_AREA[to_sq] & !to_sq.bitboard();
+    loop {
+        let (sq, ok) = area.pop_square();
+        if !ok {
+            break;
+        }
+        let p = state.piece_at_square(sq);
+        if p == NO_PIECE || p.figure() == PAWN {
+            continue;
+        }
+        if p.color() == state.turn {
+            gain -= p.figure().value();
+        } else if p.figure() == KING {
+            return MATE_BOUND;
+        } else {
+            gain += p.figure().value();
+        }
+    }
+    gain
+}
+
+/// returns the static evaluation of the state in centipawns from the point of view of the side to move
+pub fn evaluate(state: &State) -> Score {
+    let score = evaluate_color(state, WHITE) - evaluate_color(state, BLACK);
+    if state.turn == WHITE {
+        score
+    } else {
+        -score
+    }
+}
@@ -0,0 +1,180 @@
+use crate::constants::*;
+use crate::piece::*;
+use crate::square::*;
+use crate::zobrist::*;
+
+/// HISTORY_MAX bounds the history scores, updates approach it ever more slowly
+const HISTORY_MAX: Score = 16384;
+
+/// HISTORY_MAX_BONUS caps the bonus of a single update, so that deep cutoffs do not saturate the table
+const HISTORY_MAX_BONUS: Score = 1600;
+
+/// HISTORY_INDEX_SIZE tells the number of history indices, one per piece on a square
+const HISTORY_INDEX_SIZE: usize = PIECE_ARRAY_SIZE * BOARD_AREA;
+
+/// CONTINUATION_FIGURES tells the number of figures the continuation history tells apart, all lancers count as one
+const CONTINUATION_FIGURES: usize = FIG_MAX - (LANCER_MAX - LANCER);
+
+/// CONTINUATION_INDEX_SIZE tells the number of continuation history indices, one per piece in use on a square
+const CONTINUATION_INDEX_SIZE: usize = 2 * CONTINUATION_FIGURES * BOARD_AREA;
+
+/// CONTINUATION_PLIES tells how many of the preceding moves the continuation history follows up
+pub const CONTINUATION_PLIES: usize = 2;
+
+/// returns the history index of a move, the piece standing on the to square after the move and that square
+///
+/// a lancer is indexed by the direction it takes up, so that reorientations on the same square get
+/// their own entries
+pub fn history_index(mv: Move) -> usize {
+    let piece = mv.moving_piece();
+    let fig = match mv.lancer_direction() {
+        NO_FIGURE => piece.figure(),
+        direction => direction,
+    };
+    color_figure(piece.color(), fig) * BOARD_AREA + mv.to_sq()
+}
+
+/// returns the continuation history index of a move, the moving piece and the to square
+///
+/// the lancer directions share one figure and the unused LANCER code is skipped, which keeps the
+/// table of index pairs at a few megabytes
+fn continuation_index(mv: Move) -> usize {
+    let piece = mv.moving_piece();
+    let fig = piece.figure();
+    let compact_fig = if fig < LANCER_MIN {
+        fig - PAWN
+    } else if fig <= LANCER_MAX {
+        LANCER - PAWN
+    } else {
+        fig - PAWN - (LANCER_MAX - LANCER)
+    };
+    (compact_fig * 2 + piece.color()) * BOARD_AREA + mv.to_sq()
+}
+
+/// History records which quiet moves caused cutoffs, to order quiet moves in later nodes
+///
+/// killers are kept per ply, the butterfly history per history index, countermoves and continuation
+/// history are keyed by the preceding moves as well
+pub struct History {
+    killers: [[Move; NUM_KILLERS]; MAX_PLY],
+    butterfly: Vec<Score>,
+    /// countermoves maps the history index of a move to the quiet move that last refuted it
+    countermoves: Vec<Move>,
+    /// continuation maps a pair of continuation indices, the earlier move first, to a score
+    continuation: Vec<i16>,
+}
+
+/// adds a bonus to a history score, the closer the score is to HISTORY_MAX the less it moves
+fn apply_bonus(score: Score, bonus: Score) -> Score {
+    score + bonus - score * bonus.abs() / HISTORY_MAX
+}
+
+/// History defaults to a new empty History
+impl Default for History {
+    fn default() -> History {
+        History::new()
+    }
+}
+
+/// History implementation
+impl History {
+    /// returns a new empty History
+    pub fn new() -> History {
+        History {
+            killers: [[0; NUM_KILLERS]; MAX_PLY],
+            butterfly: vec![0; HISTORY_INDEX_SIZE],
+            countermoves: vec![0; HISTORY_INDEX_SIZE],
+            continuation: vec![0; CONTINUATION_INDEX_SIZE * CONTINUATION_INDEX_SIZE],
+        }
+    }
+
+    /// ages the history between searches, killers are cleared and scores halved, countermoves are kept
+    pub fn age(&mut self) {
+        self.killers = [[0; NUM_KILLERS]; MAX_PLY];
+        for score in self.butterfly.iter_mut() {
+            *score /= 2;
+        }
+        for score in self.continuation.iter_mut() {
+            *score /= 2;
+        }
+    }
+
+    /// returns the killers of ply
+    pub fn killers(&self, ply: usize) -> [Move; NUM_KILLERS] {
+        self.killers[ply]
+    }
+
+    /// returns the countermove of the previous move, 0 if unknown or there is no previous move
+    pub fn counter_move(&self, previous: Move) -> Move {
+        if previous == 0 {
+            return 0;
+        }
+        self.countermoves[history_index(previous)]
+    }
+
+    /// returns the butterfly history score of a quiet move
+    pub fn butterfly_score(&self, mv: Move) -> Score {
+        self.butterfly[history_index(mv)]
+    }
+
+    /// returns the continuation history score of a quiet move following up an earlier move, 0 for no move
+    pub fn continuation_score(&self, earlier: Move, mv: Move) -> Score {
+        if earlier == 0 {
+            return 0;
+        }
+        self.continuation
+            [continuation_index(earlier) * CONTINUATION_INDEX_SIZE + continuation_index(mv)]
+            as Score
+    }
+
+    /// returns the ordering score of a quiet move, previous lists the preceding moves, the last one first
+    pub fn quiet_score(&self, mv: Move, previous: &[Move; CONTINUATION_PLIES]) -> Score {
+        self.butterfly_score(mv)
+            + previous
+                .iter()
+                .map(|earlier| self.continuation_score(*earlier, mv))
+                .sum::<Score>()
+    }
+
+    /// records that the quiet move best caused a cutoff at ply after the quiets tried before it failed,
+    /// previous lists the preceding moves, the last one first
+    pub fn update(
+        &mut self,
+        ply: usize,
+        depth: usize,
+        best: Move,
+        tried: &[Move],
+        previous: &[Move; CONTINUATION_PLIES],
+    ) {
+        if self.killers[ply][0] != best {
+            for i in (1..NUM_KILLERS).rev() {
+                self.killers[ply][i] = self.killers[ply][i - 1];
+            }
+            self.killers[ply][0] = best;
+        }
+        if previous[0] != 0 {
+            self.countermoves[history_index(previous[0])] = best;
+        }
+        let bonus = std::cmp::min((depth * depth) as Score, HISTORY_MAX_BONUS);
+        self.add_bonus(best, bonus, previous);
+        for mv in tried.iter() {
+            if *mv != best {
+                self.add_bonus(*mv, -bonus, previous);
+            }
+        }
+    }
+
+    /// adds bonus to the butterfly and continuation history scores of a quiet move
+    fn add_bonus(&mut self, mv: Move, bonus: Score, previous: &[Move; CONTINUATION_PLIES]) {
+        let index = history_index(mv);
+        self.butterfly[index] = apply_bonus(self.butterfly[index], bonus);
+        let index = continuation_index(mv);
+        for earlier in previous.iter() {
+            if *earlier != 0 {
+                let entry = &mut self.continuation
+                    [continuation_index(*earlier) * CONTINUATION_INDEX_SIZE + index];
+                *entry = apply_bonus(*entry as Score, bonus) as i16;
+            }
+        }
+    }
+}
@@ -1,15 +1,974 @@
+pub mod betza;
 pub mod bitboard;
+pub mod bughouse;
 pub mod constants;
+pub mod eval;
+pub mod history;
 pub mod lineargame;
+pub mod movelist;
+pub mod movepicker;
+pub mod perfthash;
+pub mod perftsuite;
 pub mod piece;
+pub mod search;
 pub mod square;
 pub mod state;
+pub mod timeman;
+pub mod transtable;
 pub mod uci;
+pub mod zobrist;
 
 #[cfg(test)]
 mod tests {
+    use crate::betza::*;
+    use crate::bughouse::*;
+    use crate::constants::*;
+    use crate::eval::*;
+    use crate::history::*;
+    use crate::lineargame::*;
+    use crate::movepicker::*;
+    use crate::perfthash::*;
+    use crate::perftsuite::*;
+    use crate::piece::*;
+    use crate::search::*;
+    use crate::square::*;
+    use crate::state::*;
+    use crate::timeman::*;
+    use crate::transtable::*;
+    use crate::uci::*;
+
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn betza_pieces() {
+        let archbishop = BetzaPiece::new("archbishop", "BN").expect("valid notation");
+        assert_eq!(archbishop.attack(SQUARE_E4, WHITE, 0).count_ones(), 13 + 8);
+        let gold = BetzaPiece::new("gold", "WfF").expect("valid notation");
+        assert_eq!(gold.attack(SQUARE_E4, WHITE, 0).count_ones(), 6);
+        assert_eq!(
+            gold.attack(SQUARE_E4, BLACK, 0) & SQUARE_D3.bitboard(),
+            SQUARE_D3.bitboard()
+        );
+        let right_ferz = BetzaPiece::new("rightferz", "frFlW").expect("valid notation");
+        assert_eq!(
+            right_ferz.attack(SQUARE_E4, WHITE, 0),
+            SQUARE_F5.bitboard() | SQUARE_D4.bitboard()
+        );
+        assert_eq!(
+            right_ferz.attack(SQUARE_E4, BLACK, 0),
+            SQUARE_D3.bitboard() | SQUARE_F4.bitboard()
+        );
+        assert!(BetzaPiece::new("dabbaba", "D").is_err());
+    }
+
+    #[test]
+    fn custom_pieces_play() {
+        for content in [
+            "knightrider n NN 500",
+            "a a BN 800\nb a RN 800",
+            "a aa BN 800",
+        ]
+        .iter()
+        {
+            assert!(parse_betza_pieces(content)
+                .and_then(|pieces| register_custom_pieces(&pieces))
+                .is_err());
+        }
+        let pieces =
+            parse_betza_pieces("archbishop a BN 875\ngold G WfF 400").expect("valid pieces");
+        register_custom_pieces(&pieces).expect("valid pieces");
+        assert_eq!(CUSTOM_MIN.value(), 875);
+        assert_eq!(color_figure(WHITE, CUSTOM_MIN + 1).fen_symbol(), "G");
+        let mut game = LinearGame::new();
+        game.init_from_fen(VARIANT_STANDARD, "4k3/8/8/8/8/8/8/A3K3 w - - 0 1")
+            .expect("valid fen");
+        // 7 diagonal and 2 knight moves of the archbishop, 5 king moves
+        assert_eq!(game.perft(1).0, 14);
+        let mut state = State::new();
+        state
+            .init_from_fen(VARIANT_STANDARD, "4k3/8/3A4/8/8/8/8/4K3 b - - 0 1")
+            .expect("valid fen");
+        assert_eq!(state.report_fen(), "4k3/8/3A4/8/8/8/8/4K3 b - - 0 1");
+        assert!(state.is_in_check(BLACK));
+        // a black gold steps forward diagonally towards the first rank
+        state
+            .init_from_fen(VARIANT_STANDARD, "4k3/8/8/8/3g4/8/8/4K3 b - - 0 1")
+            .expect("valid fen");
+        assert_eq!(
+            state.color_figure_mobility_at_square(
+                SQUARE_D4,
+                MoveGenMode::All,
+                BLACK,
+                CUSTOM_MIN + 1
+            ),
+            SQUARE_D5.bitboard()
+                | SQUARE_D3.bitboard()
+                | SQUARE_C4.bitboard()
+                | SQUARE_E4.bitboard()
+                | SQUARE_C3.bitboard()
+                | SQUARE_E3.bitboard()
+        );
+        for (variant, fen) in [
+            (VARIANT_STANDARD, "r3k2r/8/2g5/8/8/5A2/8/R3K2R w KQkq - 0 1"),
+            (VARIANT_ATOMIC, "4k3/3p4/8/1A6/8/8/8/4K3 w - - 0 1"),
+        ]
+        .iter()
+        {
+            state.init_from_fen(*variant, fen).expect("valid fen");
+            check_hash_rec(&state, 2);
+            check_unmake_rec(&mut state, 2);
+        }
+        assert!(state
+            .init_from_fen(VARIANT_BUGHOUSE, "4k3/8/8/8/8/8/8/A3K3[] w - - 0 1")
+            .is_err());
+    }
+
+    #[test]
+    fn bughouse_capture_feeds_partner_pocket() {
+        let mut game = BughouseGame::new();
+        game.set_from_bfen("4k3/8/8/8/8/8/3p4/4K3[] w - - 0 1 | 4k3/8/8/8/8/8/8/4K3[] b - - 0 1")
+            .expect("valid fen");
+        game.push(0, Move::ft(SQUARE_E1, SQUARE_D2))
+            .expect("history has room");
+        assert_eq!(game.boards[1].current().pocket_count(BLACK, PAWN), 1);
+        assert_eq!(
+            game.report_bfen(),
+            "4k3/8/8/8/8/8/3K4/8[] b - - 0 1 | 4k3/8/8/8/8/8/8/4K3[p] b - - 0 1"
+        );
+        game.pop();
+        assert_eq!(game.boards[1].current().pocket_count(BLACK, PAWN), 0);
+        assert_eq!(
+            game.boards[0].current().piece_at_square(SQUARE_D2),
+            color_figure(BLACK, PAWN)
+        );
+        let bfen = game.report_bfen();
+        assert!(game
+            .set_from_bfen("4k3/8/8/8/8/8/8/4K3[] w - - 0 1")
+            .is_err());
+        assert!(game
+            .set_from_bfen("4k3/8/8/8/8/8/8/4K3[] w - - 0 1 | 4k3/8/8/8/8/8/8/4K3[x] b - - 0 1")
+            .is_err());
+        assert_eq!(game.report_bfen(), bfen);
+    }
+
+    #[test]
+    fn placement_sets_castling_rights_after_setup() {
+        let mut state = State::new();
+        state
+            .init_from_fen(
+                VARIANT_PLACEMENT,
+                "r1bqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKB1R[Nn] w - - 0 1",
+            )
+            .expect("valid fen");
+        assert!(state.in_setup_phase());
+        state.make_move(Move::drop(KNIGHT, SQUARE_G1));
+        state.make_move(Move::drop(KNIGHT, SQUARE_B8));
+        assert!(!state.in_setup_phase());
+        // black's setup drop completes a full move, so the fullmove number advances
+        assert_eq!(
+            state.report_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 2"
+        );
+        // the side to move must not run out of drops before the opponent
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKB1R[N] b - - 0 1",
+            "r1bqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[n] w - - 0 1",
+        ]
+        .iter()
+        {
+            assert!(state.set_from_fen(fen).is_err());
+        }
+        state
+            .set_from_fen("r1bqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[n] b - - 0 1")
+            .expect("valid fen");
+        assert!(state.in_setup_phase());
+    }
+
+    #[test]
+    fn game_results() {
+        let result = |variant: Variant, fen: &str| {
+            let mut state = State::new();
+            state.init_from_fen(variant, fen).expect("valid fen");
+            state.game_result()
+        };
+        assert_eq!(
+            result(
+                VARIANT_STANDARD,
+                "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"
+            ),
+            GameResult::Checkmate { winner: BLACK }
+        );
+        assert_eq!(
+            result(VARIANT_STANDARD, "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+            GameResult::Stalemate
+        );
+        assert_eq!(
+            result(VARIANT_STANDARD, "8/8/4k3/8/8/2B5/4K3/8 w - - 0 1"),
+            GameResult::InsufficientMaterial
+        );
+        assert_eq!(
+            result(VARIANT_EIGHTPIECE, "8/8/4k3/8/8/2S5/4K1N1/8 w - - 0 1"),
+            GameResult::Ongoing
+        );
+        assert_eq!(
+            result(VARIANT_STANDARD, "8/8/4k3/8/8/2R5/4K3/8 w - - 100 80"),
+            GameResult::FiftyMoveRule
+        );
+        assert_eq!(
+            result(VARIANT_ATOMIC, "8/8/8/8/8/8/8/4K3 b - - 0 1"),
+            GameResult::KingExploded { winner: WHITE }
+        );
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        let mut game = LinearGame::new();
+        game.init(VARIANT_STANDARD);
+        let shuffle = [
+            Move::ft(SQUARE_G1, SQUARE_F3),
+            Move::ft(SQUARE_G8, SQUARE_F6),
+            Move::ft(SQUARE_F3, SQUARE_G1),
+            Move::ft(SQUARE_F6, SQUARE_G8),
+        ];
+        for i in 0..8 {
+            game.push(shuffle[i % 4]).expect("history has room");
+            if i == 3 {
+                assert!(game.is_search_repetition());
+                assert_eq!(game.game_result(), GameResult::Ongoing);
+            }
+        }
+        assert_eq!(game.repetitions(), 3);
+        assert_eq!(game.game_result(), GameResult::ThreefoldRepetition);
+        game.push(Move::ft(SQUARE_E2, SQUARE_E4))
+            .expect("history has room");
+        assert_eq!(game.repetitions(), 1);
+    }
+
+    #[test]
+    fn long_game_history() {
+        let mut game = LinearGame::new();
+        game.init(VARIANT_STANDARD);
+        let shuffle = [
+            Move::ft(SQUARE_G1, SQUARE_F3),
+            Move::ft(SQUARE_G8, SQUARE_F6),
+            Move::ft(SQUARE_F3, SQUARE_G1),
+            Move::ft(SQUARE_F6, SQUARE_G8),
+        ];
+        for i in 0..4 * MAX_STATES {
+            game.push(shuffle[i % 4]).expect("history grows on demand");
+        }
+        assert_eq!(game.state_ptr, 4 * MAX_STATES);
+        assert!(game.reserve(MAX_GAME_PLIES).is_err());
+    }
+
+    #[test]
+    fn move_kinds() {
+        let parse = |variant: Variant, fen: &str, uci: &str| {
+            let mut state = State::new();
+            state.init_from_fen(variant, fen).expect("valid fen");
+            let (mv, ok) = state.move_from_uci(uci);
+            assert!(ok, "{} should be legal in {}", uci, fen);
+            assert_eq!(mv.uci(), uci);
+            mv
+        };
+        let castle = parse(
+            VARIANT_STANDARD,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "e1g1",
+        );
+        assert!(castle.is_castle());
+        assert_eq!(castle.moving_piece(), color_figure(WHITE, KING));
+        let ep = parse(
+            VARIANT_STANDARD,
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "e5f6",
+        );
+        assert!(ep.is_en_passant());
+        assert_eq!(ep.captured_piece(), color_figure(BLACK, PAWN));
+        let promotion = parse(VARIANT_STANDARD, "1n5k/P7/8/8/8/8/8/K7 w - - 0 1", "a7b8n");
+        assert!(promotion.is_promotion() && promotion.is_capture());
+        assert_eq!(promotion.promotion_figure(), KNIGHT);
+        assert_eq!(promotion.captured_piece(), color_figure(BLACK, KNIGHT));
+        let lancer = parse(
+            VARIANT_EIGHTPIECE,
+            VARIANT_INFOS[VARIANT_EIGHTPIECE].start_fen,
+            "b1d3lnw",
+        );
+        assert_eq!(lancer.kind(), MOVE_KIND_NORMAL);
+        assert_eq!(lancer.lancer_direction(), LANCERNW);
+        let push = parse(
+            VARIANT_EIGHTPIECE,
+            "jlsesqkbnr/ppp1pppp/8/3p4/4S3/8/PPPPPPPP/JLne1QKBNR w KQkq - 0 1",
+            "e4d5d6",
+        );
+        assert!(push.is_push() && !push.is_capture());
+        assert_eq!(push.push_sq(), SQUARE_D6);
+        let drop = parse(VARIANT_BUGHOUSE, "4k3/8/8/8/8/8/8/4K3[Q] w - - 0 1", "Q@e7");
+        assert_eq!(drop.kind(), MOVE_KIND_DROP);
+        assert_eq!(drop.moving_piece(), color_figure(WHITE, QUEEN));
+        let mut state = State::new();
+        state.init(VARIANT_STANDARD);
+        assert!(!state.move_from_uci("e2e5").1);
+    }
+
+    #[test]
+    fn move_picker_stages() {
+        for (variant, fen) in test_positions().iter() {
+            let mut state = State::new();
+            state.init_from_fen(*variant, fen).expect("valid fen");
+            let mut all = state.generate_pseudo_legal_moves(MoveGenMode::All);
+            let quiets = state.generate_pseudo_legal_moves(MoveGenMode::Quiet);
+            let hash_move = all[all.len() / 2];
+            let killer = quiets.last().copied().unwrap_or(0);
+            let picked: Vec<Move> = MovePicker::new(hash_move, [killer, 0])
+                .iter(&state)
+                .collect();
+            assert_eq!(picked[0], hash_move);
+            let num_violent = all.len() - quiets.len();
+            let violent_end = if quiets.contains(&hash_move) {
+                num_violent + 1
+            } else {
+                num_violent
+            };
+            for i in 2..violent_end {
+                assert!(mvv_lva(&state, picked[i - 1]) >= mvv_lva(&state, picked[i]));
+            }
+            if killer != 0 && killer != hash_move {
+                assert_eq!(picked[violent_end], killer);
+            }
+            let mut sorted = picked.clone();
+            sorted.sort();
+            all.sort();
+            assert_eq!(sorted, all, "{}", fen);
+        }
+        let mut state = State::new();
+        state.init(VARIANT_STANDARD);
+        let foreign = Move::ft(SQUARE_E7, SQUARE_E5);
+        let picked: Vec<Move> = MovePicker::new(foreign, [0, 0]).iter(&state).collect();
+        assert_eq!(picked.len(), 20);
+    }
+
+    #[test]
+    fn quiet_move_history() {
+        let parse = |state: &State, uci: &str| {
+            let (mv, ok) = state.move_from_uci(uci);
+            assert!(ok, "{} should be legal", uci);
+            mv
+        };
+        let mut state = State::new();
+        state.init(VARIANT_STANDARD);
+        let knight = parse(&state, "g1f3");
+        let rook_pawn = parse(&state, "h2h3");
+        let mut history = History::new();
+        history.update(0, 4, knight, &[rook_pawn, knight], &[0, 0]);
+        assert_eq!(history.killers(0), [knight, 0]);
+        assert!(history.butterfly_score(knight) > 0 && history.butterfly_score(rook_pawn) < 0);
+        let picked: Vec<Move> = {
+            let mut picker = MovePicker::new_for_search(0, [0, 0], 0, [0, 0]);
+            std::iter::from_fn(|| picker.next_move_with_history(&state, &history)).collect()
+        };
+        assert_eq!((picked[0], picked[19]), (knight, rook_pawn));
+        let opening = parse(&state, "e2e4");
+        state.make_move(opening);
+        let reply = parse(&state, "e7e5");
+        history.update(1, 3, reply, &[], &[opening, 0]);
+        assert_eq!(history.counter_move(opening), reply);
+        assert!(history.continuation_score(opening, reply) > 0);
+        let picked: Vec<Move> = MovePicker::new_for_search(0, [0, 0], reply, [opening, 0])
+            .iter(&state)
+            .collect();
+        assert_eq!(picked[0], reply);
+        let score = history.butterfly_score(knight);
+        history.age();
+        assert_eq!(history.killers(0), [0, 0]);
+        assert_eq!(history.butterfly_score(knight), score / 2);
+        assert_eq!(history.counter_move(opening), reply);
+        state.init(VARIANT_EIGHTPIECE);
+        let lancer_moves: Vec<Move> = state
+            .generate_pseudo_legal_moves(MoveGenMode::Quiet)
+            .into_iter()
+            .filter(|mv| mv.to_sq() == SQUARE_D3 && mv.lancer_direction() != NO_FIGURE)
+            .collect();
+        assert_eq!(lancer_moves.len(), NUM_LANCERS);
+        let mut indices: Vec<usize> = lancer_moves.iter().map(|mv| history_index(*mv)).collect();
+        indices.sort();
+        indices.dedup();
+        assert_eq!(indices.len(), NUM_LANCERS);
+        for mv in state.generate_pseudo_legal_moves(MoveGenMode::Quiet) {
+            history.update(2, 2, mv, &[], &[mv, 0]);
+            assert!(history.continuation_score(mv, mv) > 0);
+        }
+    }
+
+    #[test]
+    fn perft_divide_sums_to_perft() {
+        let mut game = LinearGame::new();
+        game.init(VARIANT_STANDARD);
+        let counts = game.perft_divide(3);
+        assert_eq!(counts.len(), 20);
+        assert_eq!(counts.iter().map(|(_, nodes)| nodes).sum::<usize>(), 8902);
+        assert_eq!(game.perft(3).0, 8902);
+        assert_eq!(game.perft_clone(3).map(|result| result.0), Ok(8902));
+    }
+
+    #[test]
+    fn threaded_perft_matches_perft() {
+        let mut game = LinearGame::new();
+        for variant in [VARIANT_STANDARD, VARIANT_EIGHTPIECE, VARIANT_ATOMIC].iter() {
+            game.init(*variant);
+            let nodes = game.perft(3).0;
+            for threads in 1..4 {
+                assert_eq!(game.perft_threaded(3, threads, 0).0, nodes);
+                assert_eq!(game.perft_threaded(3, threads, 1).0, nodes);
+            }
+        }
+    }
+
+    #[test]
+    fn perft_suites() {
+        for name in ["standard", "atomic", "eightpiece"].iter() {
+            let path = format!("{}/perft/{}.epd", env!("CARGO_MANIFEST_DIR"), name);
+            let entries = load_perft_suite(&path).expect("perft suite should load");
+            let result = run_perft_suite(&entries, 100_000, false);
+            assert!(result.passed > 0, "{} ran no checks", name);
+            assert_eq!(result.failed, 0, "{:?}", result.failures);
+        }
+        assert!(parse_perft_suite("8/8/8/8/8/8/8/K6k w - - ;D1 x").is_err());
+    }
+
+    #[test]
+    fn perft_stats() {
+        let stats = |variant: Variant, fen: &str, depth: usize| {
+            let mut game = LinearGame::new();
+            game.init_from_fen(variant, fen).expect("valid fen");
+            let stats = game.perft_stats(depth);
+            assert_eq!(stats.nodes, game.perft(depth).0);
+            stats
+        };
+        let kiwipete = stats(
+            VARIANT_STANDARD,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            3,
+        );
+        assert_eq!(
+            (
+                kiwipete.captures,
+                kiwipete.en_passants,
+                kiwipete.castles,
+                kiwipete.promotions,
+                kiwipete.checks,
+                kiwipete.checkmates
+            ),
+            (17102, 45, 3162, 0, 993, 1)
+        );
+        let position3 = stats(
+            VARIANT_STANDARD,
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            4,
+        );
+        assert_eq!(
+            (
+                position3.captures,
+                position3.en_passants,
+                position3.checks,
+                position3.discovered_checks,
+                position3.double_checks,
+                position3.checkmates
+            ),
+            (3348, 123, 1680, 106, 0, 17)
+        );
+        let eightpiece = stats(
+            VARIANT_EIGHTPIECE,
+            VARIANT_INFOS[VARIANT_EIGHTPIECE].start_fen,
+            2,
+        );
+        assert!(eightpiece.lancer_reorientations > 0);
+        let push = stats(
+            VARIANT_EIGHTPIECE,
+            "jlsesqkbnr/ppp1pppp/8/3p4/4S3/8/PPPPPPPP/JLne1QKBNR w KQkq - 0 1",
+            1,
+        );
+        assert!(push.sentry_pushes > 0);
+    }
+
+    #[test]
+    fn search_finds_mates() {
+        let search = |variant: Variant, fen: &str, depth: usize| {
+            let mut game = LinearGame::new();
+            game.init_from_fen(variant, fen).expect("valid fen");
+            let limits = SearchLimits {
+                depth,
+                ..SearchLimits::default()
+            };
+            let info = Search::new(Arc::new(TransTable::new(1))).search(&mut game, limits, |_| {});
+            assert_eq!(game.current().report_fen(), {
+                let mut state = State::new();
+                state.init_from_fen(variant, fen).expect("valid fen");
+                state.report_fen()
+            });
+            (info.best_move().uci(), score_uci(info.score))
+        };
+        assert_eq!(
+            search(VARIANT_STANDARD, "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 4),
+            ("a1a8".to_string(), "mate 1".to_string())
+        );
+        assert_eq!(
+            search(VARIANT_ATOMIC, "4k3/4p3/8/3N4/8/8/8/4K3 w - - 0 1", 3),
+            ("d5e7".to_string(), "mate 1".to_string())
+        );
+        let (_, score) = search(VARIANT_STANDARD, "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 3);
+        assert_eq!(score, "cp 0");
+        let (best, _) = search(
+            VARIANT_EIGHTPIECE,
+            VARIANT_INFOS[VARIANT_EIGHTPIECE].start_fen,
+            2,
+        );
+        assert!(!best.is_empty());
+        assert_eq!(score_uci(-MATE_SCORE + 4), "mate -2");
+        assert_eq!(score_uci(MATE_SCORE - 3), "mate 2");
+    }
+
+    #[test]
+    fn quiescence_sees_recaptures() {
+        let mut game = LinearGame::new();
+        game.init_from_fen(VARIANT_STANDARD, "4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1")
+            .expect("valid fen");
+        let limits = SearchLimits {
+            depth: 1,
+            ..SearchLimits::default()
+        };
+        let info = Search::new(Arc::new(TransTable::new(1))).search(&mut game, limits, |_| {});
+        assert_ne!(info.best_move().uci(), "d1d5");
+        assert!(info.score > 500);
+        let gain = |fen: &str, uci: &str| {
+            let mut state = State::new();
+            state.init_from_fen(VARIANT_ATOMIC, fen).expect("valid fen");
+            let (mv, ok) = state.move_from_uci(uci);
+            assert!(ok, "{} should be legal in {}", uci, fen);
+            capture_gain(&state, mv)
+        };
+        assert_eq!(gain("4k3/2r5/1n6/3N4/8/8/8/4K3 w - - 0 1", "d5b6"), 500);
+        assert_eq!(gain("4k3/2r5/1n6/3N4/8/8/8/4K3 w - - 0 1", "d5f4"), 0);
+        assert_eq!(
+            gain("4k3/4p3/8/3N4/8/8/8/4K3 w - - 0 1", "d5e7"),
+            MATE_BOUND
+        );
+    }
+
+    #[test]
+    fn transposition_table() {
+        let tt = TransTable::new(1);
+        assert!(tt.probe(0x1234).is_none());
+        tt.store(0x1234, Move::ft(SQUARE_E2, SQUARE_E4), 25, 5, Bound::Lower);
+        let entry = tt.probe(0x1234).expect("entry was stored");
+        assert_eq!(
+            (entry.mv, entry.score, entry.depth, entry.bound),
+            (Move::ft(SQUARE_E2, SQUARE_E4), 25, 5, Bound::Lower)
+        );
+        tt.store(0x1234, 0, -40, 6, Bound::Upper);
+        let entry = tt.probe(0x1234).expect("entry was replaced");
+        assert_eq!(entry.mv, Move::ft(SQUARE_E2, SQUARE_E4));
+        assert_eq!((entry.score, entry.bound), (-40, Bound::Upper));
+        tt.clear();
+        assert!(tt.probe(0x1234).is_none());
+        let mate_in_3 = MATE_SCORE - 5;
+        assert_eq!(score_from_tt(score_to_tt(mate_in_3, 2), 4), mate_in_3 - 2);
+        assert_eq!(score_from_tt(score_to_tt(-mate_in_3, 2), 2), -mate_in_3);
+        let mut uci = create_default_uci();
+        uci.process_uci_command("setoption name Hash value 1".to_string());
+        assert_eq!(uci.tt.len(), TransTable::new(1).len());
+        assert!(uci
+            .process_setoption_command(&["name", "Hash", "value", "0"])
+            .is_err());
+        assert!(TransTable::try_new(usize::MAX >> 20).is_err());
+    }
+
+    #[test]
+    fn time_management() {
+        let limits = TimeLimits {
+            wtime: 60_000,
+            btime: 1_000,
+            move_overhead: 30,
+            ..TimeLimits::default()
+        };
+        let white = TimeManager::new(&limits, WHITE);
+        assert!(white.soft_limit > 1_000 && white.soft_limit < white.hard_limit);
+        assert!(white.hard_limit <= 60_000 * 3 / 4);
+        let black = TimeManager::new(&limits, BLACK);
+        assert!(black.hard_limit < 1_000 && black.soft_limit <= black.hard_limit);
+        let mut unstable = white;
+        unstable.iteration_done(true);
+        assert!(!white.can_start_iteration(white.soft_limit));
+        assert!(unstable.can_start_iteration(white.soft_limit));
+        assert!(!unstable.can_start_iteration(white.hard_limit));
+        let last_move = TimeManager::new(
+            &TimeLimits {
+                wtime: 100,
+                movestogo: 1,
+                move_overhead: 30,
+                ..TimeLimits::default()
+            },
+            WHITE,
+        );
+        assert_eq!(last_move.hard_limit, 70);
+        let movetime = TimeManager::new(
+            &TimeLimits {
+                movetime: 500,
+                move_overhead: 30,
+                ..TimeLimits::default()
+            },
+            WHITE,
+        );
+        assert_eq!((movetime.soft_limit, movetime.hard_limit), (470, 470));
+        let missing_clock = TimeManager::new(
+            &TimeLimits {
+                wtime: 60_000,
+                ..TimeLimits::default()
+            },
+            BLACK,
+        );
+        assert!(missing_clock.hard_limit > 0 && missing_clock.hard_limit < 60_000 / 2);
+        let untimed = TimeManager::new(&TimeLimits::default(), WHITE);
+        assert!(untimed.can_start_iteration(u64::MAX / 2) && !untimed.hard_limit_reached(u64::MAX));
+
+        let mut game = LinearGame::new();
+        game.init(VARIANT_STANDARD);
+        let limits = SearchLimits {
+            time: TimeLimits {
+                movetime: 200,
+                ..TimeLimits::default()
+            },
+            ..SearchLimits::default()
+        };
+        let info = Search::new(Arc::new(TransTable::new(1))).search(&mut game, limits, |_| {});
+        assert!(info.best_move() != 0 && info.millis < 2_000);
+    }
+
+    #[test]
+    fn lazy_smp() {
+        let mut game = LinearGame::new();
+        game.init_from_fen(VARIANT_STANDARD, "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1")
+            .expect("valid fen");
+        let tt = Arc::new(TransTable::new(1));
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut histories = Vec::new();
+        let limits = SearchLimits {
+            depth: 4,
+            ..SearchLimits::default()
+        };
+        let info = search_threaded(
+            &game,
+            tt.clone(),
+            stop.clone(),
+            limits,
+            SearchOptions::new(),
+            &mut histories,
+            3,
+            |_| {},
+        );
+        assert_eq!(histories.len(), 3);
+        assert_eq!(info.best_move().uci(), "a1a8");
+        assert_eq!(score_uci(info.score), "mate 1");
+        assert!(stop.load(Ordering::Relaxed));
+        game.init(VARIANT_EIGHTPIECE);
+        stop.store(false, Ordering::Relaxed);
+        let limits = SearchLimits {
+            nodes: 20_000,
+            ..SearchLimits::default()
+        };
+        let info = search_threaded(
+            &game,
+            tt,
+            stop,
+            limits,
+            SearchOptions::new(),
+            &mut histories,
+            2,
+            |_| {},
+        );
+        assert!(info.best_move() != 0 && info.nodes >= 20_000);
+        assert_eq!(histories.len(), 2);
+    }
+
+    #[test]
+    fn selective_search() {
+        let search = |options: SearchOptions, variant: Variant, fen: &str, depth: usize| {
+            let mut game = LinearGame::new();
+            game.init_from_fen(variant, fen).expect("valid fen");
+            let limits = SearchLimits {
+                depth,
+                ..SearchLimits::default()
+            };
+            let mut search = Search::new(Arc::new(TransTable::new(1)));
+            search.options = options;
+            let info = search.search(&mut game, limits, |_| {});
+            (score_uci(info.score), info.nodes)
+        };
+        let all_off = SearchOptions {
+            null_move: false,
+            lmr: false,
+            futility: false,
+            reverse_futility: false,
+            razoring: false,
+            ..SearchOptions::new()
+        };
+        for options in [SearchOptions::new(), all_off].iter() {
+            for (variant, fen, depth, score) in [
+                (
+                    VARIANT_STANDARD,
+                    "k7/8/2K5/8/8/8/8/7Q w - - 0 1",
+                    4,
+                    "mate 2",
+                ),
+                (
+                    VARIANT_ATOMIC,
+                    "4k3/4p3/8/3N4/8/8/8/4K3 w - - 0 1",
+                    3,
+                    "mate 1",
+                ),
+                (VARIANT_STANDARD, "8/8/8/8/8/2k5/8/K7 w - - 0 1", 5, "cp 0"),
+            ]
+            .iter()
+            {
+                assert_eq!(search(*options, *variant, fen, *depth).0, *score);
+            }
+        }
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+        let (_, pruned_nodes) = search(SearchOptions::new(), VARIANT_STANDARD, fen, 5);
+        let (_, full_nodes) = search(all_off, VARIANT_STANDARD, fen, 5);
+        assert!(pruned_nodes < full_nodes);
+        let mut uci = create_default_uci();
+        uci.process_uci_command("setoption name NullMove value false".to_string());
+        uci.process_uci_command("setoption name lmr value false".to_string());
+        assert!(!uci.search_options.null_move && !uci.search_options.lmr);
+        assert!(uci.search_options.futility && uci.search_options.razoring);
+        assert!(uci
+            .process_setoption_command(&["name", "Razoring", "value", "maybe"])
+            .is_err());
+    }
+
+    #[test]
+    fn search_extensions() {
+        // the smothered mate needs three plies, the checks extend it into a depth 2 search
+        let mut game = LinearGame::new();
+        game.init_from_fen(VARIANT_STANDARD, "r6k/6pp/7N/8/8/1Q6/8/6K1 w - - 0 1")
+            .expect("valid fen");
+        let mut search = Search::new(Arc::new(TransTable::new(1)));
+        search.options.futility = false;
+        search.options.razoring = false;
+        let limits = SearchLimits {
+            depth: 2,
+            ..SearchLimits::default()
+        };
+        let info = search.search(&mut game, limits, |_| {});
+        assert_eq!(score_uci(info.score), "mate 2");
+        assert_eq!(info.best_move().uci(), "b3g8");
+        let mut state = State::new();
+        state
+            .init_from_fen(VARIANT_ATOMIC, "4k3/4p3/8/3N4/8/8/8/4K3 w - - 0 1")
+            .expect("valid fen");
+        assert_eq!(state.explosion_threats(WHITE), SQUARE_E7.bitboard());
+        assert_eq!(state.explosion_threats(BLACK), 0);
+        let mut game = LinearGame::new();
+        game.init(VARIANT_EIGHTPIECE);
+        let limits = SearchLimits {
+            depth: 7,
+            ..SearchLimits::default()
+        };
+        let info = Search::new(Arc::new(TransTable::new(1))).search(&mut game, limits, |_| {});
+        assert_eq!(info.depth, 7);
+    }
+
+    #[test]
+    fn multi_pv() {
+        let lines = |fen: &str, depth: usize, multi_pv: usize| {
+            let mut game = LinearGame::new();
+            game.init_from_fen(VARIANT_STANDARD, fen)
+                .expect("valid fen");
+            let mut search = Search::new(Arc::new(TransTable::new(1)));
+            search.options.multi_pv = multi_pv;
+            let limits = SearchLimits {
+                depth,
+                ..SearchLimits::default()
+            };
+            let mut lines: Vec<SearchInfo> = Vec::new();
+            let info = search.search(&mut game, limits, |line| lines.push(line.clone()));
+            lines.retain(|line| line.depth == info.depth);
+            assert_eq!((info.score, &info.pv), (lines[0].score, &lines[0].pv));
+            lines
+        };
+        let mate = lines("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3, 3);
+        assert_eq!(
+            mate.iter().map(|line| line.multipv).collect::<Vec<usize>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(mate[0].best_move().uci(), "a1a8");
+        assert_eq!(score_uci(mate[0].score), "mate 1");
+        assert!(mate[1].score < mate[0].score);
+        assert_ne!(mate[1].best_move(), mate[2].best_move());
+        assert!(mate[1..]
+            .iter()
+            .all(|line| line.best_move().uci() != "a1a8"));
+        let forced = lines("k7/8/1K6/8/8/8/8/7R b - - 0 1", 2, 4);
+        assert_eq!(forced.len(), 1);
+        assert_eq!(forced[0].best_move().uci(), "a8b8");
+        assert!(forced[0].uci().starts_with("info depth 2 multipv 1 score"));
+        let mut uci = create_default_uci();
+        uci.process_uci_command("setoption name MultiPV value 4".to_string());
+        assert_eq!(uci.search_options.multi_pv, 4);
+        assert!(uci
+            .process_setoption_command(&["name", "MultiPV", "value", "0"])
+            .is_err());
+    }
+
+    #[test]
+    fn uci_position_and_go() {
+        let mut uci = create_default_uci();
+        uci.process_uci_command("variant standard".to_string());
+        uci.process_uci_command("position startpos moves e2e4 e7e5 g1f3".to_string());
+        assert_eq!(
+            uci.linear_game.current().report_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+        assert!(uci
+            .process_position_command(&["startpos", "moves", "e2e5"])
+            .is_err());
+        assert!(uci
+            .process_position_command(&["fen", "8/8/8/8/8/8/8/4K3", "x", "-", "-"])
+            .is_err());
+        assert!(uci
+            .process_position_command(&["fen", "8/8/9/8/8/8/8/4K3", "w", "-", "-"])
+            .is_err());
+        assert_eq!(
+            uci.linear_game.current().report_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+        uci.process_uci_command("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1".to_string());
+        let limits = uci
+            .parse_go_limits(&["wtime", "1000", "btime", "900", "winc", "10"])
+            .expect("valid go arguments");
+        assert_eq!((limits.time.wtime, limits.time.winc), (1000, 10));
+        assert!(!limits.infinite);
+        assert!(uci.parse_go_limits(&[]).expect("bare go").infinite);
+        assert!(uci.parse_go_limits(&["depth"]).is_err());
+        assert!(uci.parse_go_limits(&["ponder", "wtime", "1000"]).is_err());
+        uci.process_uci_command("go depth 3".to_string());
+        uci.wait_for_search();
+        uci.process_uci_command("go infinite".to_string());
+        uci.process_uci_command("stop".to_string());
+    }
+
+    #[test]
+    fn perft_hash() {
+        let hash = PerftHash::new(0);
+        assert_eq!(hash.len(), 1);
+        hash.store(0x1234, 3, 8902);
+        assert_eq!(hash.probe(0x1234, 3), (8902, true));
+        assert!(!hash.probe(0x1234, 2).1);
+        assert!(!hash.probe(0x4321, 3).1);
+        let mut game = LinearGame::new();
+        game.init_from_fen(
+            VARIANT_STANDARD,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .expect("valid fen");
+        let hash = PerftHash::new(1);
+        assert_eq!(game.perft_bulk_rec(3, Some(&hash)), 97862);
+        assert_eq!(game.perft_bulk_rec(3, Some(&hash)), 97862);
+    }
+
+    fn check_hash_rec(state: &State, depth: usize) {
+        assert_eq!(state.hash(), state.compute_hash(), "{}", state.report_fen());
+        if depth == 0 {
+            return;
+        }
+        for mv in state.generate_legal_moves(MoveGenMode::All) {
+            let mut child = state.clone();
+            child.make_move(mv);
+            check_hash_rec(&child, depth - 1);
+        }
+    }
+
+    fn check_unmake_rec(state: &mut State, depth: usize) {
+        if depth == 0 {
+            return;
+        }
+        let fen = state.report_fen();
+        let hash = state.hash();
+        for mv in state.generate_legal_moves(MoveGenMode::All) {
+            let undo = state.make_move_with_undo(mv);
+            check_unmake_rec(state, depth - 1);
+            state.unmake_move(&undo);
+            assert_eq!(state.report_fen(), fen, "unmake {}", mv.uci());
+            assert_eq!(state.hash(), hash, "unmake {}", mv.uci());
+        }
+    }
+
+    #[test]
+    fn unmake_restores_state() {
+        let mut positions = test_positions();
+        positions.push((
+            VARIANT_EIGHTPIECE,
+            "jlsesqkbnr/ppp1pppp/8/3p4/4S3/8/PPPPPPPP/JLne1QKBNR w KQkq - 0 1",
+        ));
+        for (variant, fen) in positions.iter() {
+            let mut state = State::new();
+            state.init_from_fen(*variant, fen).expect("valid fen");
+            check_unmake_rec(&mut state, 2);
+        }
+    }
+
+    fn test_positions() -> Vec<(Variant, &'static str)> {
+        vec![
+            (VARIANT_STANDARD, VARIANT_INFOS[VARIANT_STANDARD].start_fen),
+            (
+                VARIANT_STANDARD,
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            ),
+            (
+                VARIANT_EIGHTPIECE,
+                VARIANT_INFOS[VARIANT_EIGHTPIECE].start_fen,
+            ),
+            (
+                VARIANT_ATOMIC,
+                "rnbqkb1r/pppppppp/8/8/4n3/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            ),
+            (
+                VARIANT_BUGHOUSE,
+                "r3k2r/pppq1ppp/8/3Pp3/8/8/PPP2PPP/R3K2R[QNpb] w KQkq e6 0 1",
+            ),
+            (
+                VARIANT_PLACEMENT,
+                VARIANT_INFOS[VARIANT_PLACEMENT].start_fen,
+            ),
+        ]
+    }
+
+    #[test]
+    fn incremental_hash() {
+        for (variant, fen) in test_positions().iter() {
+            let mut state = State::new();
+            state.init_from_fen(*variant, fen).expect("valid fen");
+            check_hash_rec(&state, 2);
+        }
+        let play = |moves: &[Move]| {
+            let mut state = State::new();
+            state.init(VARIANT_STANDARD);
+            for mv in moves.iter() {
+                state.make_move(*mv);
+            }
+            state.hash()
+        };
+        let nf3 = Move::ft(SQUARE_G1, SQUARE_F3);
+        let nc3 = Move::ft(SQUARE_B1, SQUARE_C3);
+        let nf6 = Move::ft(SQUARE_G8, SQUARE_F6);
+        assert_eq!(play(&[nf3, nf6, nc3]), play(&[nc3, nf6, nf3]));
+        assert_ne!(play(&[nf3, nf6]), play(&[nc3, nf6]));
+    }
 }
@@ -1,18 +1,84 @@
 //use crate::bitboard::*;
 use crate::constants::*;
-//use crate::piece::*;
+use crate::movelist::*;
+use crate::perfthash::*;
+use crate::piece::*;
 use crate::square::*;
 use crate::state::*;
 
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 use std::time::Instant;
 
+/// PerftStats records the breakdown of the leaf nodes of a perft by the kind of the last move
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PerftStats {
+    pub nodes: usize,
+    /// captures include en passant captures
+    pub captures: usize,
+    pub en_passants: usize,
+    pub castles: usize,
+    pub promotions: usize,
+    /// checks include discovered and double checks
+    pub checks: usize,
+    pub discovered_checks: usize,
+    pub double_checks: usize,
+    pub checkmates: usize,
+    pub sentry_pushes: usize,
+    /// lancer reorientations count lancer moves that change the direction of the lancer
+    pub lancer_reorientations: usize,
+}
+
+/// PerftStats implementation
+impl PerftStats {
+    /// adds the counts of other stats
+    pub fn add(&mut self, other: &PerftStats) {
+        self.nodes += other.nodes;
+        self.captures += other.captures;
+        self.en_passants += other.en_passants;
+        self.castles += other.castles;
+        self.promotions += other.promotions;
+        self.checks += other.checks;
+        self.discovered_checks += other.discovered_checks;
+        self.double_checks += other.double_checks;
+        self.checkmates += other.checkmates;
+        self.sentry_pushes += other.sentry_pushes;
+        self.lancer_reorientations += other.lancer_reorientations;
+    }
+
+    /// returns the counts as name and count pairs, in the order of the published perft tables
+    pub fn counts(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("nodes", self.nodes),
+            ("captures", self.captures),
+            ("en passants", self.en_passants),
+            ("castles", self.castles),
+            ("promotions", self.promotions),
+            ("checks", self.checks),
+            ("discovered checks", self.discovered_checks),
+            ("double checks", self.double_checks),
+            ("checkmates", self.checkmates),
+            ("sentry pushes", self.sentry_pushes),
+            ("lancer reorientations", self.lancer_reorientations),
+        ]
+    }
+}
+
 /// LinearGame represents a single variantion chess game
+#[derive(Clone)]
 pub struct LinearGame {
     pub states: Vec<State>,
     pub state_ptr: usize,
     pub nodes: usize,
 }
 
+/// LinearGame defaults to a new empty LinearGame
+impl Default for LinearGame {
+    fn default() -> LinearGame {
+        LinearGame::new()
+    }
+}
+
 /// LinearGame implementation
 impl LinearGame {
     /// returns a new empty LinearGame
@@ -31,7 +97,45 @@ impl LinearGame {
 
     /// returns the game as pretty printable string
     pub fn pretty_print_string(&mut self) -> String {
-        self.current().pretty_print_string()
+        let result = self.game_result();
+        self.current().pretty_print_string_with_result(result)
+    }
+
+    /// returns the number of occurences of the current position in the game, including the current one
+    ///
+    /// no position before the last irreversible move can repeat, so the scan goes back at most
+    /// halfmove clock plies, only states with the same side to move are compared
+    pub fn repetitions(&self) -> usize {
+        let state = &self.states[self.state_ptr];
+        let back = std::cmp::min(state.halfmove_clock(), self.state_ptr);
+        let mut count = 1;
+        let mut ply = 2;
+        while ply <= back {
+            if self.states[self.state_ptr - ply].hash() == state.hash() {
+                count += 1;
+            }
+            ply += 2;
+        }
+        count
+    }
+
+    /// tells whether the current position occured at least count times
+    pub fn is_repetition(&self, count: usize) -> bool {
+        self.repetitions() >= count
+    }
+
+    /// tells whether the search should score the current position as draw by repetition
+    pub fn is_search_repetition(&self) -> bool {
+        self.is_repetition(SEARCH_REPETITION_COUNT)
+    }
+
+    /// returns the result of the game, taking the history into account for threefold repetition
+    pub fn game_result(&self) -> GameResult {
+        let result = self.states[self.state_ptr].game_result();
+        if !result.is_over() && self.is_repetition(GAME_REPETITION_COUNT) {
+            return GameResult::ThreefoldRepetition;
+        }
+        result
     }
 
     /// prints the game
@@ -45,48 +149,285 @@ impl LinearGame {
         self.current().init(variant);
     }
 
-    /// pushes a move
-    pub fn push(&mut self, mv: Move) {
+    /// initializes game to variant and fen, the game is left unchanged if the fen is invalid
+    pub fn init_from_fen(&mut self, variant: Variant, fen: &str) -> Result<(), String> {
+        let mut state = State::new();
+        state.init_from_fen(variant, fen)?;
+        self.state_ptr = 0;
+        *self.current() = state;
+        Ok(())
+    }
+
+    /// makes sure the history can hold plies more states beyond the current one without growing
+    pub fn reserve(&mut self, plies: usize) -> Result<(), String> {
+        let needed = self.state_ptr + plies + 1;
+        if needed > MAX_GAME_PLIES {
+            return Err(format!(
+                "game history full : {} plies requested , at most {} plies allowed",
+                needed - 1,
+                MAX_GAME_PLIES - 1
+            ));
+        }
+        if needed > self.states.len() {
+            self.states.resize(needed, State::new());
+        }
+        Ok(())
+    }
+
+    /// pushes a move, the history grows on demand up to MAX_GAME_PLIES
+    pub fn push(&mut self, mv: Move) -> Result<(), String> {
+        self.reserve(1)?;
         self.state_ptr += 1;
-        self.states[self.state_ptr] = self.states[self.state_ptr - 1].clone();
+        let (played, next) = self.states.split_at_mut(self.state_ptr);
+        next[0].clone_from(&played[self.state_ptr - 1]);
         self.current().make_move(mv);
+        Ok(())
     }
 
     /// pops a state
     pub fn pop(&mut self) {
-        if self.state_ptr <= 0 {
+        if self.state_ptr == 0 {
             return;
         }
         self.state_ptr -= 1;
     }
 
     /// pushes a move by index in state move buff
-    pub fn push_by_index(&mut self, index: usize) -> bool {
+    pub fn push_by_index(&mut self, index: usize) -> Result<(), String> {
         if index < self.current().move_buff.len() {
             let mv = self.current().move_buff[index].mv;
-            self.push(mv);
-            return true;
+            return self.push(mv);
         }
-        false
+        Err(format!("invalid move index {}", index + 1))
     }
 
+    /// counts the legal leaf nodes at depth using make and unmake on the current state
     pub fn perft_rec(&mut self, depth: usize) {
-        self.nodes += 1;
         if depth == 0 {
+            self.nodes += 1;
             return;
         }
-        let moves = self.current().generate_pseudo_legal_moves(MoveGenMode::All);
+        let mut moves = MoveList::new();
+        self.current()
+            .generate_legal_moves_into(MoveGenMode::All, &mut moves);
         for mv in moves.iter() {
-            self.push(*mv);
+            let undo = self.current().make_move_with_undo(*mv);
             self.perft_rec(depth - 1);
+            self.current().unmake_move(&undo);
+        }
+    }
+
+    /// returns the number of legal leaf nodes at depth, at depth 1 the legal moves are counted without making them
+    ///
+    /// if a hash is given, subtree node counts are looked up in and stored to it
+    pub fn perft_bulk_rec(&mut self, depth: usize, hash: Option<&PerftHash>) -> usize {
+        if depth == 0 {
+            return 1;
+        }
+        let key = self.current().hash();
+        if depth > 1 {
+            if let Some(hash) = hash {
+                let (nodes, ok) = hash.probe(key, depth);
+                if ok {
+                    return nodes;
+                }
+            }
+        }
+        let mut moves = MoveList::new();
+        self.current()
+            .generate_legal_moves_into(MoveGenMode::All, &mut moves);
+        if depth == 1 {
+            return moves.len();
+        }
+        let mut nodes = 0;
+        for mv in moves.iter() {
+            let undo = self.current().make_move_with_undo(*mv);
+            nodes += self.perft_bulk_rec(depth - 1, hash);
+            self.current().unmake_move(&undo);
+        }
+        if let Some(hash) = hash {
+            hash.store(key, depth, nodes);
+        }
+        nodes
+    }
+
+    /// returns the perft stats at depth, the leaf nodes are classified by the move leading to them
+    pub fn perft_stats(&mut self, depth: usize) -> PerftStats {
+        let mut stats = PerftStats::default();
+        if depth == 0 {
+            stats.nodes = 1;
+            return stats;
+        }
+        let mut moves = MoveList::new();
+        self.current()
+            .generate_legal_moves_into(MoveGenMode::All, &mut moves);
+        for mv in moves.iter() {
+            if depth > 1 {
+                let undo = self.current().make_move_with_undo(*mv);
+                stats.add(&self.perft_stats(depth - 1));
+                self.current().unmake_move(&undo);
+                continue;
+            }
+            self.add_leaf_stats(*mv, &mut stats);
+        }
+        stats
+    }
+
+    /// classifies the leaf node reached by a legal move into stats
+    fn add_leaf_stats(&mut self, mv: Move, stats: &mut PerftStats) {
+        stats.nodes += 1;
+        if mv.is_capture() {
+            stats.captures += 1;
+        }
+        if mv.is_en_passant() {
+            stats.en_passants += 1;
+        }
+        if mv.is_castle() {
+            stats.castles += 1;
+        }
+        if mv.is_promotion() {
+            stats.promotions += 1;
+        }
+        if mv.is_push() {
+            stats.sentry_pushes += 1;
+        }
+        if mv.lancer_direction() != NO_FIGURE && mv.lancer_direction() != mv.moving_piece().figure()
+        {
+            stats.lancer_reorientations += 1;
+        }
+        let landing = self.current().landing_squares(mv);
+        let undo = self.current().make_move_with_undo(mv);
+        let state = self.current();
+        let checkers = state.checkers(state.turn);
+        if checkers != 0 {
+            stats.checks += 1;
+            if checkers & !landing != 0 {
+                stats.discovered_checks += 1;
+            }
+            if checkers.count_ones() > 1 {
+                stats.double_checks += 1;
+            }
+            let mut replies = MoveList::new();
+            state.generate_legal_moves_into(MoveGenMode::All, &mut replies);
+            if replies.is_empty() {
+                stats.checkmates += 1;
+            }
+        }
+        self.current().unmake_move(&undo);
+    }
+
+    /// returns the legal leaf node counts at depth for each legal root move, sorted by uci
+    pub fn perft_divide(&mut self, depth: usize) -> Vec<(Move, usize)> {
+        let mut moves = MoveList::new();
+        self.current()
+            .generate_legal_moves_into(MoveGenMode::All, &mut moves);
+        let mut counts: Vec<(Move, usize)> = Vec::new();
+        for mv in moves.iter() {
+            self.nodes = 0;
+            let undo = self.current().make_move_with_undo(*mv);
+            self.perft_rec(depth.saturating_sub(1));
+            self.current().unmake_move(&undo);
+            counts.push((*mv, self.nodes));
+        }
+        counts.sort_by_key(|(mv, _)| mv.uci());
+        counts
+    }
+
+    /// runs perft with bulk counting splitting the root moves across threads, each with its own game,
+    /// returns nodes, seconds and kNodes per second
+    ///
+    /// if hash mib is not 0, the threads share a perft hash of that size in MiB
+    pub fn perft_threaded(
+        &mut self,
+        depth: usize,
+        threads: usize,
+        hash_mib: usize,
+    ) -> (usize, f32, f32) {
+        let start = Instant::now();
+        let mut moves = MoveList::new();
+        self.current()
+            .generate_legal_moves_into(MoveGenMode::All, &mut moves);
+        let nodes = if depth == 0 {
+            1
+        } else {
+            let root = self.current().clone();
+            let next_move = AtomicUsize::new(0);
+            let perft_hash = if hash_mib > 0 {
+                Some(PerftHash::new(hash_mib))
+            } else {
+                None
+            };
+            let moves = &moves;
+            let next_move = &next_move;
+            let perft_hash = perft_hash.as_ref();
+            thread::scope(|scope| {
+                let workers: Vec<_> = (0..threads.max(1))
+                    .map(|_| {
+                        let root = root.clone();
+                        scope.spawn(move || {
+                            let mut game = LinearGame::new();
+                            game.states[0] = root;
+                            let mut nodes = 0;
+                            loop {
+                                let i = next_move.fetch_add(1, Ordering::Relaxed);
+                                if i >= moves.len() {
+                                    break;
+                                }
+                                let undo = game.current().make_move_with_undo(moves[i]);
+                                nodes += game.perft_bulk_rec(depth - 1, perft_hash);
+                                game.current().unmake_move(&undo);
+                            }
+                            nodes
+                        })
+                    })
+                    .collect();
+                workers
+                    .into_iter()
+                    .map(|worker| worker.join().expect("perft worker panicked"))
+                    .sum()
+            })
+        };
+        self.nodes = nodes;
+        let secs = start.elapsed().as_secs_f32();
+        (nodes, secs, (nodes as f32) / secs / 1000.0)
+    }
+
+    /// counts the legal leaf nodes at depth pushing and popping cloned states, kept for speed comparison
+    ///
+    /// returns an error if a state cannot be pushed, the game is back at the starting state in any case
+    pub fn perft_clone_rec(&mut self, depth: usize) -> Result<(), String> {
+        if depth == 0 {
+            self.nodes += 1;
+            return Ok(());
+        }
+        let moves = self.current().generate_legal_moves(MoveGenMode::All);
+        for mv in moves.iter() {
+            self.push(*mv)?;
+            let result = self.perft_clone_rec(depth - 1);
             self.pop();
+            result?;
         }
+        Ok(())
     }
 
+    /// runs perft with make and unmake, returns nodes, seconds and kNodes per second
     pub fn perft(&mut self, depth: usize) -> (usize, f32, f32) {
         self.nodes = 0;
         let start = Instant::now();
         self.perft_rec(depth);
+        self.perft_result(start)
+    }
+
+    /// runs perft cloning states, returns nodes, seconds and kNodes per second, an error if a state cannot be pushed
+    pub fn perft_clone(&mut self, depth: usize) -> Result<(usize, f32, f32), String> {
+        self.nodes = 0;
+        let start = Instant::now();
+        self.perft_clone_rec(depth)?;
+        Ok(self.perft_result(start))
+    }
+
+    /// returns the nodes counted since start, the seconds elapsed and kNodes per second
+    fn perft_result(&self, start: Instant) -> (usize, f32, f32) {
         let duration = start.elapsed();
         let secs = ((duration.as_secs() as f32) * 1e9 + (duration.subsec_nanos() as f32)) / 1e9;
         (self.nodes, secs, (self.nodes as f32) / secs / 1000.0)
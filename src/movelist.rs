@@ -0,0 +1,84 @@
+use crate::constants::*;
+use crate::square::*;
+
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+
+/// MoveList is a stack allocated list of moves with fixed capacity, generators push into it
+///
+/// only the first len moves are initialized, so that creating a list does not fill its whole capacity
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [MaybeUninit<Move>; MAX_MOVES],
+    len: usize,
+}
+
+/// MoveList defaults to a new empty MoveList
+impl Default for MoveList {
+    fn default() -> MoveList {
+        MoveList::new()
+    }
+}
+
+/// MoveList implementation
+impl MoveList {
+    /// returns a new empty MoveList
+    pub fn new() -> MoveList {
+        MoveList {
+            moves: [MaybeUninit::uninit(); MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    /// pushes a move, the generators never exceed MAX_MOVES moves for a position
+    pub fn push(&mut self, mv: Move) {
+        debug_assert!(
+            self.len < MAX_MOVES,
+            "move list full, more than {} moves",
+            MAX_MOVES
+        );
+        self.moves[self.len] = MaybeUninit::new(mv);
+        self.len += 1;
+    }
+
+    /// removes all moves
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// keeps only the moves for which the predicate is true, preserving their order
+    pub fn retain<F: FnMut(Move) -> bool>(&mut self, mut f: F) {
+        let mut kept = 0;
+        for i in 0..self.len {
+            let mv = self[i];
+            if f(mv) {
+                self[kept] = mv;
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
+
+    /// returns the moves as vector
+    pub fn to_vec(&self) -> Vec<Move> {
+        self[..].to_vec()
+    }
+}
+
+/// MoveList dereferences to the slice of its moves
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        // the first len moves were initialized by push
+        unsafe { std::slice::from_raw_parts(self.moves.as_ptr() as *const Move, self.len) }
+    }
+}
+
+/// MoveList dereferences mutably to the slice of its moves
+impl DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut [Move] {
+        // the first len moves were initialized by push
+        unsafe { std::slice::from_raw_parts_mut(self.moves.as_mut_ptr() as *mut Move, self.len) }
+    }
+}
@@ -0,0 +1,229 @@
+use crate::constants::*;
+use crate::history::*;
+use crate::movelist::*;
+use crate::piece::*;
+use crate::square::*;
+use crate::state::*;
+
+/// MovePicker stages, in the order they are visited
+const STAGE_HASH_MOVE: usize = 0;
+const STAGE_GEN_CAPTURES: usize = 1;
+const STAGE_CAPTURES: usize = 2;
+const STAGE_KILLERS: usize = 3;
+const STAGE_GEN_QUIETS: usize = 4;
+const STAGE_QUIETS: usize = 5;
+const STAGE_DONE: usize = 6;
+
+/// MVV_LVA_SCALE weighs the victim against the attacker, so that any more valuable victim comes first
+const MVV_LVA_SCALE: Score = 16;
+
+/// returns the MVV-LVA score of a violent move, promotions and sentry pushes are scored along with captures
+pub fn mvv_lva(state: &State, mv: Move) -> Score {
+    let mut score: Score = 0;
+    if mv.is_capture() {
+        score += MVV_LVA_SCALE * mv.captured_piece().figure().value()
+            - mv.moving_piece().figure().value();
+    } else if mv.is_push() {
+        score += state.piece_at_square(mv.to_sq()).figure().value();
+    }
+    if mv.is_promotion() {
+        score += MVV_LVA_SCALE * (mv.promotion_figure().value() - PAWN.value());
+    }
+    score
+}
+
+/// NUM_REFUTATIONS tells the number of quiet moves tried before the other quiets, the killers and the countermove
+const NUM_REFUTATIONS: usize = NUM_KILLERS + 1;
+
+/// MovePicker generates pseudo legal moves lazily in stages : hash move, captures by MVV-LVA, killers and
+/// countermove, quiets by history
+///
+/// moves of a later stage are only generated once the earlier stages are exhausted, so that a cutoff
+/// on an early move saves the generation of the rest, the caller still has to check legality
+pub struct MovePicker {
+    stage: usize,
+    hash_move: Move,
+    /// refutations lists the killers followed by the countermove
+    refutations: [Move; NUM_REFUTATIONS],
+    refutation_index: usize,
+    /// previous moves are the moves preceding the node, the last one first, for continuation history
+    previous_moves: [Move; CONTINUATION_PLIES],
+    moves: MoveList,
+    scores: [Score; MAX_MOVES],
+    index: usize,
+    /// violent only tells whether the picker stops after the captures, as quiescence search needs
+    violent_only: bool,
+}
+
+/// MovePicker implementation
+impl MovePicker {
+    /// returns a new MovePicker, hash move and killers may be 0 if unknown
+    pub fn new(hash_move: Move, killers: [Move; NUM_KILLERS]) -> MovePicker {
+        MovePicker::new_for_search(hash_move, killers, 0, [0; CONTINUATION_PLIES])
+    }
+
+    /// returns a new MovePicker that also tries the countermove early, moves may be 0 if unknown,
+    /// previous moves list the moves preceding the node, the last one first
+    pub fn new_for_search(
+        hash_move: Move,
+        killers: [Move; NUM_KILLERS],
+        counter_move: Move,
+        previous_moves: [Move; CONTINUATION_PLIES],
+    ) -> MovePicker {
+        let mut refutations = [0; NUM_REFUTATIONS];
+        refutations[0..NUM_KILLERS].copy_from_slice(&killers);
+        if !killers.contains(&counter_move) {
+            refutations[NUM_KILLERS] = counter_move;
+        }
+        MovePicker {
+            stage: STAGE_HASH_MOVE,
+            hash_move,
+            refutations,
+            refutation_index: 0,
+            previous_moves,
+            moves: MoveList::new(),
+            scores: [0; MAX_MOVES],
+            index: 0,
+            violent_only: false,
+        }
+    }
+
+    /// returns a new MovePicker that only picks violent moves by MVV-LVA
+    pub fn new_violent() -> MovePicker {
+        let mut picker = MovePicker::new(0, [0; NUM_KILLERS]);
+        picker.violent_only = true;
+        picker
+    }
+
+    /// scores the generated moves of the current stage
+    fn score_moves(&mut self, state: &State) {
+        for i in 0..self.moves.len() {
+            self.scores[i] = mvv_lva(state, self.moves[i]);
+        }
+    }
+
+    /// returns the best scored move not yet picked, selection sort keeps picking cheap when a cutoff comes early
+    fn pick_best(&mut self) -> Option<Move> {
+        while self.index < self.moves.len() {
+            let mut best = self.index;
+            for i in self.index + 1..self.moves.len() {
+                if self.scores[i] > self.scores[best] {
+                    best = i;
+                }
+            }
+            self.moves.swap(self.index, best);
+            self.scores.swap(self.index, best);
+            let mv = self.moves[self.index];
+            self.index += 1;
+            if mv != self.hash_move {
+                return Some(mv);
+            }
+        }
+        None
+    }
+
+    /// returns the next pseudo legal move, None when all moves have been picked
+    pub fn next_move(&mut self, state: &State) -> Option<Move> {
+        self.pick_next_move(state, None)
+    }
+
+    /// returns the next pseudo legal move like next_move, with the quiets ordered by history
+    pub fn next_move_with_history(&mut self, state: &State, history: &History) -> Option<Move> {
+        self.pick_next_move(state, Some(history))
+    }
+
+    /// returns the next pseudo legal move, quiets are ordered by history if there is one
+    fn pick_next_move(&mut self, state: &State, history: Option<&History>) -> Option<Move> {
+        loop {
+            match self.stage {
+                STAGE_HASH_MOVE => {
+                    self.stage = STAGE_GEN_CAPTURES;
+                    if self.hash_move != 0 && state.is_pseudo_legal(self.hash_move) {
+                        return Some(self.hash_move);
+                    }
+                }
+                STAGE_GEN_CAPTURES => {
+                    state.generate_moves_for_color(
+                        MoveGenMode::Violent,
+                        state.turn,
+                        &mut self.moves,
+                    );
+                    self.score_moves(state);
+                    self.index = 0;
+                    self.stage = STAGE_CAPTURES;
+                }
+                STAGE_CAPTURES => {
+                    if let Some(mv) = self.pick_best() {
+                        return Some(mv);
+                    }
+                    self.stage = if self.violent_only {
+                        STAGE_DONE
+                    } else {
+                        STAGE_KILLERS
+                    };
+                }
+                STAGE_KILLERS => {
+                    if self.refutation_index >= NUM_REFUTATIONS {
+                        self.stage = STAGE_GEN_QUIETS;
+                        continue;
+                    }
+                    let refutation = self.refutations[self.refutation_index];
+                    self.refutation_index += 1;
+                    // the captures are still in the list, a violent refutation was already picked there
+                    if refutation != 0
+                        && refutation != self.hash_move
+                        && !self.moves.contains(&refutation)
+                        && state.is_pseudo_legal(refutation)
+                    {
+                        return Some(refutation);
+                    }
+                }
+                STAGE_GEN_QUIETS => {
+                    state.generate_moves_for_color(MoveGenMode::Quiet, state.turn, &mut self.moves);
+                    let refutations = self.refutations;
+                    self.moves.retain(|mv| !refutations.contains(&mv));
+                    for i in 0..self.moves.len() {
+                        self.scores[i] = match history {
+                            Some(history) => {
+                                history.quiet_score(self.moves[i], &self.previous_moves)
+                            }
+                            None => 0,
+                        };
+                    }
+                    self.index = 0;
+                    self.stage = STAGE_QUIETS;
+                }
+                STAGE_QUIETS => {
+                    if let Some(mv) = self.pick_best() {
+                        return Some(mv);
+                    }
+                    self.stage = STAGE_DONE;
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// turns the picker into an iterator over the moves of a state
+    pub fn iter(self, state: &State) -> MovePickerIter<'_> {
+        MovePickerIter {
+            picker: self,
+            state,
+        }
+    }
+}
+
+/// MovePickerIter iterates over the moves of a state picked by a MovePicker
+pub struct MovePickerIter<'a> {
+    picker: MovePicker,
+    state: &'a State,
+}
+
+/// MovePickerIter implements Iterator
+impl<'a> Iterator for MovePickerIter<'a> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        self.picker.next_move(self.state)
+    }
+}
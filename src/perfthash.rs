@@ -0,0 +1,73 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// PERFT_HASH_DEPTH_SHIFT tells the shift of the depth in the data word of a PerftHashEntry
+const PERFT_HASH_DEPTH_SHIFT: usize = 56;
+
+/// PERFT_HASH_NODES_MASK masks the node count in the data word of a PerftHashEntry
+const PERFT_HASH_NODES_MASK: u64 = (1 << PERFT_HASH_DEPTH_SHIFT) - 1;
+
+/// PerftHashEntry records the node count of a subtree as a key word and a data word
+///
+/// the key word is the position hash xored with the data word, so that a torn write by another thread
+/// fails verification instead of returning a wrong count
+struct PerftHashEntry {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// PerftHash caches perft subtree node counts keyed by position hash and depth, it can be shared between threads
+pub struct PerftHash {
+    entries: Vec<PerftHashEntry>,
+}
+
+/// PerftHash implementation
+impl PerftHash {
+    /// returns a new PerftHash using at most size mib MiB, at least one entry is allocated
+    pub fn new(size_mib: usize) -> PerftHash {
+        let entry_size = std::mem::size_of::<PerftHashEntry>();
+        let num_entries = std::cmp::max(1, (size_mib << 20) / entry_size);
+        PerftHash {
+            entries: (0..num_entries)
+                .map(|_| PerftHashEntry {
+                    key: AtomicU64::new(0),
+                    data: AtomicU64::new(0),
+                })
+                .collect(),
+        }
+    }
+
+    /// returns the number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// tells whether there are no entries, never true as at least one is allocated
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// returns the entry for a hash
+    fn entry(&self, hash: u64) -> &PerftHashEntry {
+        &self.entries[(hash % (self.entries.len() as u64)) as usize]
+    }
+
+    /// returns the cached node count of hash at depth and true, or 0 and false if not cached
+    pub fn probe(&self, hash: u64, depth: usize) -> (usize, bool) {
+        let entry = self.entry(hash);
+        let data = entry.data.load(Ordering::Relaxed);
+        let key = entry.key.load(Ordering::Relaxed);
+        if key ^ data != hash || (data >> PERFT_HASH_DEPTH_SHIFT) as usize != depth {
+            return (0, false);
+        }
+        ((data & PERFT_HASH_NODES_MASK) as usize, true)
+    }
+
+    /// stores the node count of hash at depth, replacing the previous entry
+    pub fn store(&self, hash: u64, depth: usize, nodes: usize) {
+        let entry = self.entry(hash);
+        let data =
+            ((depth as u64) << PERFT_HASH_DEPTH_SHIFT) | (nodes as u64 & PERFT_HASH_NODES_MASK);
+        entry.key.store(hash ^ data, Ordering::Relaxed);
+        entry.data.store(data, Ordering::Relaxed);
+    }
+}
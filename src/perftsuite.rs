@@ -0,0 +1,140 @@
+use crate::constants::*;
+use crate::lineargame::*;
+use crate::state::*;
+
+/// PerftSuiteEntry records a position of a perft suite with its expected node counts
+pub struct PerftSuiteEntry {
+    pub variant: Variant,
+    pub fen: String,
+    /// depths records depth and expected node count pairs
+    pub depths: Vec<(usize, usize)>,
+}
+
+/// PerftSuiteResult records the outcome of running a perft suite
+pub struct PerftSuiteResult {
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    /// failures records a description of each failed check
+    pub failures: Vec<String>,
+}
+
+/// parses a perft suite in EPD format, one "fen ;D1 n ;D2 n ..." position per line
+///
+/// '#' starts a comment, a "variant <name>" line sets the variant of the positions that follow,
+/// the default variant is Standard
+pub fn parse_perft_suite(content: &str) -> Result<Vec<PerftSuiteEntry>, String> {
+    let mut entries: Vec<PerftSuiteEntry> = Vec::new();
+    let mut variant = VARIANT_STANDARD;
+    for (i, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("variant ") {
+            variant = variant_from_name(name.trim())
+                .ok_or_else(|| format!("line {} : unknown variant {:?}", i + 1, name.trim()))?;
+            continue;
+        }
+        let mut fields = line.split(';');
+        let fen = fields.next().unwrap_or("").trim().to_string();
+        let mut depths: Vec<(usize, usize)> = Vec::new();
+        for field in fields {
+            let parts: Vec<&str> = field.split_whitespace().collect();
+            let parsed = match parts.as_slice() {
+                [depth, nodes] if depth.starts_with('D') => {
+                    match (depth[1..].parse::<usize>(), nodes.parse::<usize>()) {
+                        (Ok(depth), Ok(nodes)) => Some((depth, nodes)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            match parsed {
+                Some(depth_nodes) => depths.push(depth_nodes),
+                None => return Err(format!("line {} : invalid depth field {:?}", i + 1, field)),
+            }
+        }
+        if depths.is_empty() {
+            return Err(format!("line {} : no depths given", i + 1));
+        }
+        State::new()
+            .init_from_fen(variant, &fen)
+            .map_err(|err| format!("line {} : {}", i + 1, err))?;
+        entries.push(PerftSuiteEntry {
+            variant,
+            fen,
+            depths,
+        });
+    }
+    Ok(entries)
+}
+
+/// loads a perft suite from an EPD file
+pub fn load_perft_suite(path: &str) -> Result<Vec<PerftSuiteEntry>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| format!("could not read {} : {}", path, err))?;
+    parse_perft_suite(&content)
+}
+
+/// runs a perft suite, depths whose expected node count exceeds max nodes are skipped
+pub fn run_perft_suite(
+    entries: &[PerftSuiteEntry],
+    max_nodes: usize,
+    verbose: bool,
+) -> PerftSuiteResult {
+    let mut result = PerftSuiteResult {
+        passed: 0,
+        failed: 0,
+        skipped: 0,
+        failures: Vec::new(),
+    };
+    let mut game = LinearGame::new();
+    for entry in entries.iter() {
+        if let Err(err) = game.init_from_fen(entry.variant, &entry.fen) {
+            result.failed += entry.depths.len();
+            result.failures.push(format!(
+                "{} {} : {}",
+                entry.variant.string(),
+                entry.fen,
+                err
+            ));
+            continue;
+        }
+        for (depth, expected) in entry.depths.iter() {
+            if *expected > max_nodes {
+                result.skipped += 1;
+                continue;
+            }
+            let (nodes, secs, knps) = game.perft(*depth);
+            let ok = nodes == *expected;
+            if verbose {
+                println!(
+                    "{} {} {} D{} expected {} got {} , time {:.2} sec(s) , nps {:.0} kNode(s)/sec",
+                    if ok { "ok    " } else { "FAILED" },
+                    entry.variant.string(),
+                    entry.fen,
+                    depth,
+                    expected,
+                    nodes,
+                    secs,
+                    knps
+                );
+            }
+            if ok {
+                result.passed += 1;
+            } else {
+                result.failed += 1;
+                result.failures.push(format!(
+                    "{} {} D{} expected {} got {}",
+                    entry.variant.string(),
+                    entry.fen,
+                    depth,
+                    expected,
+                    nodes
+                ));
+            }
+        }
+    }
+    result
+}
@@ -1,5 +1,73 @@
 use crate::constants::*;
 
+use std::sync::atomic::{AtomicI32, AtomicU8, Ordering};
+
+/// LOWER_CASE_LETTERS maps a letter index to the lower case letter, used as fen symbols of custom figures
+const LOWER_CASE_LETTERS: [&str; 26] = [
+    "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q", "r", "s",
+    "t", "u", "v", "w", "x", "y", "z",
+];
+
+/// UPPER_CASE_LETTERS maps a letter index to the upper case letter, used as fen symbols of custom figures
+const UPPER_CASE_LETTERS: [&str; 26] = [
+    "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S",
+    "T", "U", "V", "W", "X", "Y", "Z",
+];
+
+/// CUSTOM_FIGURE_SYMBOLS records the lower case fen symbol of each custom figure, 0 if the figure is not in use
+static CUSTOM_FIGURE_SYMBOLS: [AtomicU8; NUM_CUSTOM_FIGURES] = [
+    AtomicU8::new(0),
+    AtomicU8::new(0),
+    AtomicU8::new(0),
+    AtomicU8::new(0),
+];
+
+/// CUSTOM_FIGURE_VALUES records the material value of each custom figure in centipawns
+static CUSTOM_FIGURE_VALUES: [AtomicI32; NUM_CUSTOM_FIGURES] = [
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+    AtomicI32::new(0),
+];
+
+/// sets the lower case fen symbol and the value of a custom figure, a symbol of 0 takes the figure out of use
+pub fn set_custom_figure(fig: Figure, symbol: u8, value: Score) {
+    CUSTOM_FIGURE_SYMBOLS[fig - CUSTOM_MIN].store(symbol, Ordering::Relaxed);
+    CUSTOM_FIGURE_VALUES[fig - CUSTOM_MIN].store(value, Ordering::Relaxed);
+}
+
+/// returns the lower case fen symbol of a custom figure, 0 if the figure is not in use
+pub fn custom_figure_symbol(fig: Figure) -> u8 {
+    CUSTOM_FIGURE_SYMBOLS[fig - CUSTOM_MIN].load(Ordering::Relaxed)
+}
+
+/// returns the custom figure having a lower case fen symbol, NO_FIGURE if there is none
+pub fn custom_figure_for_symbol(symbol: u8) -> Figure {
+    if symbol == 0 {
+        return NO_FIGURE;
+    }
+    for fig in CUSTOM_MIN..CUSTOM_MAX + 1 {
+        if custom_figure_symbol(fig) == symbol {
+            return fig;
+        }
+    }
+    NO_FIGURE
+}
+
+/// returns the letter of a custom figure in the requested case, "?" if the figure is not in use
+fn custom_figure_letter(fig: Figure, upper_case: bool) -> &'static str {
+    let symbol = custom_figure_symbol(fig);
+    if !symbol.is_ascii_lowercase() {
+        return "?";
+    }
+    let index = (symbol - b'a') as usize;
+    if upper_case {
+        UPPER_CASE_LETTERS[index]
+    } else {
+        LOWER_CASE_LETTERS[index]
+    }
+}
+
 /// Figure type represents a chess figure as an unsigned int
 pub type Figure = usize;
 
@@ -11,16 +79,21 @@ pub trait FigureTrait {
     fn base_figure(self) -> Figure;
     /// return the lancer direction of the figure provided that it is a lancer
     fn lancer_direction(self) -> usize;
+    /// returns the material value of the figure in centipawns
+    fn value(self) -> Score;
 }
 
 impl FigureTrait for Figure {
     /// returns the fen symbol for the figure ( lower case )
     fn symbol(self) -> &'static str {
+        if self >= CUSTOM_MIN {
+            return custom_figure_letter(self, false);
+        }
         FIGURE_FEN_SYMBOLS[self]
     }
     /// returns the base figure of the figure, same as figure except for lancers, where it is LANCER
     fn base_figure(self) -> Figure {
-        if self < LANCER_MIN || self > LANCER_MAX {
+        if !(LANCER_MIN..=LANCER_MAX).contains(&self) {
             return self;
         }
         LANCER
@@ -29,8 +102,18 @@ impl FigureTrait for Figure {
     fn lancer_direction(self) -> usize {
         self - LANCER_MIN
     }
+    /// returns the material value of the figure in centipawns
+    fn value(self) -> Score {
+        if self >= CUSTOM_MIN {
+            return CUSTOM_FIGURE_VALUES[self - CUSTOM_MIN].load(Ordering::Relaxed);
+        }
+        FIGURE_VALUES[self]
+    }
 }
 
+/// Score type represents an evaluation or search score in centipawns
+pub type Score = i32;
+
 /// Color type represents a chess color
 pub type Color = usize;
 
@@ -75,7 +158,20 @@ pub fn fen_symbol_to_piece(letter: &str) -> Piece {
         "S" => color_figure(WHITE, SENTRY),
         "j" => color_figure(BLACK, JAILER),
         "J" => color_figure(WHITE, JAILER),
-        _ => NO_PIECE,
+        _ => {
+            let bytes = letter.as_bytes();
+            if bytes.len() != 1 {
+                return NO_PIECE;
+            }
+            let fig = custom_figure_for_symbol(bytes[0].to_ascii_lowercase());
+            if fig == NO_FIGURE {
+                NO_PIECE
+            } else if bytes[0].is_ascii_uppercase() {
+                color_figure(WHITE, fig)
+            } else {
+                color_figure(BLACK, fig)
+            }
+        }
     }
 }
 
@@ -103,7 +199,11 @@ pub trait PieceTrait {
 impl PieceTrait for Piece {
     /// returns the color of the piece
     fn color(self) -> Color {
-        return if self & 1 == 0 { BLACK } else { WHITE };
+        if self & 1 == 0 {
+            BLACK
+        } else {
+            WHITE
+        }
     }
     /// returns the figure of the piece
     fn figure(self) -> Figure {
@@ -111,18 +211,24 @@ impl PieceTrait for Piece {
     }
     /// returns the fen symbol for the piece
     fn fen_symbol(self) -> &'static str {
+        if self.figure() >= CUSTOM_MIN {
+            return custom_figure_letter(self.figure(), self.color() == WHITE);
+        }
         PIECE_FEN_SYMBOLS[self]
     }
     /// returns the san symbol for the piece ( capital piece letter )
     fn san_symbol(self) -> &'static str {
-        return color_figure(WHITE, self.figure()).fen_symbol();
+        color_figure(WHITE, self.figure()).fen_symbol()
     }
     /// returns the uci symbol of the piece ( lower case )
     fn uci_symbol(self) -> &'static str {
-        return self.figure().symbol();
+        self.figure().symbol()
     }
     /// returns the san letter of the piece ( upper case )
     fn san_letter(self) -> &'static str {
+        if self.figure() >= CUSTOM_MIN {
+            return custom_figure_letter(self.figure(), true);
+        }
         FIGURE_SAN_LETTERS[self.figure()]
     }
 }
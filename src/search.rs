@@ -0,0 +1,914 @@
+use crate::constants::*;
+use crate::eval::*;
+use crate::history::*;
+use crate::lineargame::*;
+use crate::movelist::*;
+use crate::movepicker::*;
+use crate::piece::*;
+use crate::square::*;
+use crate::state::*;
+use crate::timeman::*;
+use crate::transtable::*;
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// TIME_CHECK_INTERVAL tells the number of nodes between two checks of the clock and the node counters, a power of two
+const TIME_CHECK_INTERVAL: usize = 1024;
+
+/// NULL_MOVE_MIN_DEPTH tells the minimum depth at which a null move is tried
+const NULL_MOVE_MIN_DEPTH: usize = 3;
+/// NULL_MOVE_REDUCTION tells by how much a null move search is reduced on top of the ply, more at higher depths
+const NULL_MOVE_REDUCTION: usize = 2;
+
+/// LMR_MIN_DEPTH tells the minimum depth at which late moves are reduced
+const LMR_MIN_DEPTH: usize = 3;
+/// LMR_MIN_MOVES tells the number of moves searched at full depth before late moves are reduced
+const LMR_MIN_MOVES: usize = 3;
+/// MAX_QUIETS_TRIED bounds the quiet moves per node that get a history malus on a cutoff, later ones are left alone
+const MAX_QUIETS_TRIED: usize = 64;
+
+/// FUTILITY_MAX_DEPTH tells the maximum depth at which futile quiet moves are pruned
+const FUTILITY_MAX_DEPTH: usize = 3;
+/// FUTILITY_MARGIN tells by how much per ply a quiet move may at most raise the static evaluation
+const FUTILITY_MARGIN: Score = 150;
+
+/// REVERSE_FUTILITY_MAX_DEPTH tells the maximum depth at which a node is cut by its static evaluation
+const REVERSE_FUTILITY_MAX_DEPTH: usize = 6;
+/// REVERSE_FUTILITY_MARGIN tells by how much per ply the static evaluation has to exceed beta for a cut
+const REVERSE_FUTILITY_MARGIN: Score = 120;
+
+/// RAZORING_MAX_DEPTH tells the maximum depth at which hopeless nodes drop into quiescence search
+const RAZORING_MAX_DEPTH: usize = 2;
+/// RAZORING_MARGIN tells by how much per ply the static evaluation has to fall short of alpha for razoring
+const RAZORING_MARGIN: Score = 300;
+
+/// SINGULAR_MIN_DEPTH tells the minimum depth at which the transposition table move is tested for singularity
+const SINGULAR_MIN_DEPTH: usize = 6;
+/// SINGULAR_TT_DEPTH_MARGIN tells how much shallower than the node the table entry of a singular move may be
+const SINGULAR_TT_DEPTH_MARGIN: usize = 3;
+/// SINGULAR_MARGIN tells by how much per ply the other moves have to fall short of the table move
+const SINGULAR_MARGIN: Score = 2;
+
+/// DELTA_MARGIN tells how much a violent move may fall short of alpha in quiescence search before it is pruned
+const DELTA_MARGIN: Score = 200;
+
+/// SearchLimits tells when a search stops, a limit of 0 means no limit
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SearchLimits {
+    /// depth tells the maximum iteration depth
+    pub depth: usize,
+    /// nodes tells the maximum number of nodes
+    pub nodes: usize,
+    /// infinite tells that the search only stops when told to, the caller has to wait for the stop
+    pub infinite: bool,
+    /// time records the clock parameters
+    pub time: TimeLimits,
+}
+
+/// SearchInfo reports the result of a search iteration
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchInfo {
+    pub depth: usize,
+    /// multipv tells the rank of the line among the lines of a multipv search, 1 for the best
+    pub multipv: usize,
+    /// score is from the point of view of the side to move at the root
+    pub score: Score,
+    pub nodes: usize,
+    pub millis: u128,
+    /// pv records the principal variation, empty if there is no legal move
+    pub pv: Vec<Move>,
+}
+
+/// SearchInfo implementation
+impl SearchInfo {
+    /// returns the best move, 0 if there is no legal move
+    pub fn best_move(&self) -> Move {
+        self.pv.first().copied().unwrap_or(0)
+    }
+
+    /// returns the nodes searched per second
+    pub fn nps(&self) -> u128 {
+        (self.nodes as u128) * 1000 / std::cmp::max(1, self.millis)
+    }
+
+    /// returns the info as UCI info line
+    pub fn uci(&self) -> String {
+        format!(
+            "info depth {} multipv {} score {} nodes {} nps {} time {} pv {}",
+            self.depth,
+            self.multipv,
+            score_uci(self.score),
+            self.nodes,
+            self.nps(),
+            self.millis,
+            self.pv
+                .iter()
+                .map(|mv| mv.uci())
+                .collect::<Vec<String>>()
+                .join(" ")
+        )
+    }
+}
+
+/// returns a score as UCI score, "cp n" or "mate n" with n in moves, n is negative when getting mated
+pub fn score_uci(score: Score) -> String {
+    if score >= MATE_BOUND {
+        format!("mate {}", (MATE_SCORE - score + 1) / 2)
+    } else if score <= -MATE_BOUND {
+        format!("mate -{}", (MATE_SCORE + score) / 2)
+    } else {
+        format!("cp {}", score)
+    }
+}
+
+/// searches the current position of the game with lazy SMP, threads searches share the transposition table
+///
+/// the main search reports through on info and decides when to stop, the helpers search the same position
+/// on their own copy of the game until then and fill the table, the returned info counts the nodes of all
+/// threads, an infinite search returns only once stop is set
+///
+/// histories holds the move ordering history of each thread, it is taken over and handed back, so that
+/// it carries over to the next search
+#[allow(clippy::too_many_arguments)]
+pub fn search_threaded<F: FnMut(&SearchInfo)>(
+    game: &LinearGame,
+    tt: Arc<TransTable>,
+    stop: Arc<AtomicBool>,
+    limits: SearchLimits,
+    options: SearchOptions,
+    histories: &mut Vec<History>,
+    threads: usize,
+    on_info: F,
+) -> SearchInfo {
+    let mut taken = std::mem::take(histories).into_iter();
+    let mut next_history = || taken.next().unwrap_or_default();
+    let mut main = Search::with_history(tt.clone(), next_history());
+    main.options = options;
+    main.stop = stop.clone();
+    let helpers: Vec<Search> = (1..threads)
+        .map(|thread_index| {
+            let mut helper = Search::with_history(tt.clone(), next_history());
+            helper.options = options;
+            // helpers only deepen the shared hash table, the main thread reports all lines
+            helper.options.multi_pv = 1;
+            helper.stop = stop.clone();
+            helper.thread_index = thread_index;
+            helper
+        })
+        .collect();
+    main.helper_node_counters = helpers
+        .iter()
+        .map(|helper| helper.node_counter.clone())
+        .collect();
+    let helper_limits = SearchLimits {
+        infinite: true,
+        ..SearchLimits::default()
+    };
+    let info = thread::scope(|scope| {
+        let mut spawned_helpers = Vec::new();
+        for mut helper in helpers {
+            let mut game = game.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("helper {}", helper.thread_index))
+                .stack_size(SEARCH_STACK_SIZE)
+                .spawn_scoped(scope, move || {
+                    helper.search(&mut game, helper_limits, |_| {});
+                    helper.history
+                });
+            match spawned {
+                Ok(handle) => spawned_helpers.push(handle),
+                // the main thread alone still gives a result
+                Err(_) => break,
+            }
+        }
+        let mut game = game.clone();
+        let info = main.search(&mut game, limits, on_info);
+        while limits.infinite && !stop.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(1));
+        }
+        stop.store(true, Ordering::Relaxed);
+        for handle in spawned_helpers {
+            if let Ok(history) = handle.join() {
+                histories.push(history);
+            }
+        }
+        info
+    });
+    let nodes = main.total_nodes();
+    histories.insert(0, main.history);
+    SearchInfo { nodes, ..info }
+}
+
+/// SEARCH_OPTION_NAMES lists the UCI check options switching the selective search techniques
+pub const SEARCH_OPTION_NAMES: [&str; 5] =
+    ["NullMove", "LMR", "Futility", "ReverseFutility", "Razoring"];
+
+/// SearchOptions configures the search, the selective search techniques are all enabled by default
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchOptions {
+    /// multi pv tells the number of best lines to search and report, 1 for a normal search
+    pub multi_pv: usize,
+    pub null_move: bool,
+    pub lmr: bool,
+    pub futility: bool,
+    pub reverse_futility: bool,
+    pub razoring: bool,
+}
+
+/// SearchOptions default to a single line with all techniques enabled
+impl Default for SearchOptions {
+    fn default() -> SearchOptions {
+        SearchOptions::new()
+    }
+}
+
+/// SearchOptions implementation
+impl SearchOptions {
+    /// returns new SearchOptions with a single line and all techniques enabled
+    pub fn new() -> SearchOptions {
+        SearchOptions {
+            multi_pv: 1,
+            null_move: true,
+            lmr: true,
+            futility: true,
+            reverse_futility: true,
+            razoring: true,
+        }
+    }
+}
+
+/// Search holds the state of an iterative deepening negamax alpha-beta search
+pub struct Search {
+    pub nodes: usize,
+    /// tt is the transposition table, it may be shared with other searches
+    pub tt: Arc<TransTable>,
+    pub options: SearchOptions,
+    /// stop tells the search to stop as soon as possible, it may be set from another thread
+    pub stop: Arc<AtomicBool>,
+    /// thread index tells the index of the search thread, the main thread is 0, helpers are numbered from 1
+    pub thread_index: usize,
+    /// node counter publishes the nodes of the search to other threads
+    pub node_counter: Arc<AtomicUsize>,
+    /// helper node counters are the node counters of the helpers of a main thread
+    pub helper_node_counters: Vec<Arc<AtomicUsize>>,
+    limits: SearchLimits,
+    time_manager: TimeManager,
+    stopped: bool,
+    start: Instant,
+    /// hashes records the hashes of the game history and the search path, for repetition detection
+    hashes: Vec<u64>,
+    /// pv records the triangular principal variation table, row ply holds the pv from ply
+    pv: Vec<[Move; MAX_PLY]>,
+    pv_len: [usize; MAX_PLY],
+    /// root depth is the depth of the current iteration, extensions stop at twice that ply
+    root_depth: usize,
+    /// moves records the move made at each ply of the search path, 0 for a null move
+    moves: [Move; MAX_PLY],
+    /// excluded records the move skipped at each ply by a singular extension search, 0 for none
+    excluded: [Move; MAX_PLY],
+    /// history orders the quiet moves, it is aged at the start of each search
+    pub history: History,
+    /// root excluded lists the root moves of the lines already found in a multipv iteration
+    root_excluded: Vec<Move>,
+}
+
+/// Search implementation
+impl Search {
+    /// returns a new Search using the transposition table tt
+    pub fn new(tt: Arc<TransTable>) -> Search {
+        Search::with_history(tt, History::new())
+    }
+
+    /// returns a new Search using the transposition table tt that orders quiet moves with history
+    pub fn with_history(tt: Arc<TransTable>, history: History) -> Search {
+        Search {
+            nodes: 0,
+            tt,
+            options: SearchOptions::new(),
+            stop: Arc::new(AtomicBool::new(false)),
+            thread_index: 0,
+            node_counter: Arc::new(AtomicUsize::new(0)),
+            helper_node_counters: Vec::new(),
+            limits: SearchLimits::default(),
+            time_manager: TimeManager::new(&TimeLimits::default(), WHITE),
+            stopped: false,
+            start: Instant::now(),
+            hashes: Vec::new(),
+            pv: vec![[0; MAX_PLY]; MAX_PLY],
+            pv_len: [0; MAX_PLY],
+            root_depth: 0,
+            moves: [0; MAX_PLY],
+            excluded: [0; MAX_PLY],
+            history,
+            root_excluded: Vec::new(),
+        }
+    }
+
+    /// searches the current position of the game with iterative deepening until a limit is reached,
+    /// calls on info after each completed iteration and returns the info of the last completed one
+    ///
+    /// the game is left in the position it was in
+    pub fn search<F: FnMut(&SearchInfo)>(
+        &mut self,
+        game: &mut LinearGame,
+        limits: SearchLimits,
+        mut on_info: F,
+    ) -> SearchInfo {
+        self.nodes = 0;
+        self.limits = limits;
+        self.stopped = false;
+        self.start = Instant::now();
+        self.node_counter.store(0, Ordering::Relaxed);
+        if self.thread_index == 0 {
+            self.tt.new_search();
+        }
+        self.history.age();
+        self.hashes = game.states[0..game.state_ptr + 1]
+            .iter()
+            .map(|state| state.hash())
+            .collect();
+        let max_depth = if limits.depth == 0 {
+            MAX_PLY - 1
+        } else {
+            std::cmp::min(limits.depth, MAX_PLY - 1)
+        };
+        let state = game.current();
+        self.time_manager = TimeManager::new(&limits.time, state.turn);
+        let mut result = SearchInfo::default();
+        // odd helpers start one iteration later, so that helpers spread over two depths
+        let start_depth = std::cmp::min(1 + self.thread_index % 2, max_depth);
+        for depth in start_depth..max_depth + 1 {
+            if depth > 1 && !self.time_manager.can_start_iteration(self.elapsed()) {
+                break;
+            }
+            self.root_depth = depth;
+            // each line searches the root moves not yet reported by the lines before it
+            self.root_excluded.clear();
+            let mut best_line = None;
+            for multipv in 1..self.options.multi_pv + 1 {
+                let score = self.negamax(state, depth, 0, -INFINITE_SCORE, INFINITE_SCORE, false);
+                if self.stopped || (multipv > 1 && self.pv_len[0] == 0) {
+                    break;
+                }
+                let mut pv = self.pv[0][0..self.pv_len[0]].to_vec();
+                self.extend_pv_from_tt(state, &mut pv, depth);
+                let line = SearchInfo {
+                    depth,
+                    multipv,
+                    score,
+                    nodes: self.total_nodes(),
+                    millis: self.start.elapsed().as_millis(),
+                    pv,
+                };
+                on_info(&line);
+                let no_legal_move = line.pv.is_empty();
+                if let Some(mv) = line.pv.first() {
+                    self.root_excluded.push(*mv);
+                }
+                best_line = best_line.or(Some(line));
+                if no_legal_move {
+                    break;
+                }
+            }
+            let previous_best = result.best_move();
+            result = match best_line {
+                Some(line) => line,
+                None => break,
+            };
+            let score = result.score;
+            if self.stopped {
+                break;
+            }
+            self.time_manager
+                .iteration_done(depth > 1 && result.best_move() != previous_best);
+            if result.pv.is_empty() || MATE_SCORE - score.abs() <= depth as Score {
+                // no legal move or a forced mate within the horizon, deeper iterations find nothing new
+                break;
+            }
+        }
+        if result.pv.is_empty() && self.stopped {
+            // stopped within the first iteration, any legal move is better than none
+            let mut moves = MoveList::new();
+            state.generate_legal_moves_into(MoveGenMode::All, &mut moves);
+            if let Some(mv) = moves.first() {
+                result.pv.push(*mv);
+            }
+        }
+        self.node_counter.store(self.nodes, Ordering::Relaxed);
+        result.nodes = self.total_nodes();
+        result.millis = self.start.elapsed().as_millis();
+        result
+    }
+
+    /// returns the milliseconds elapsed since the start of the search
+    fn elapsed(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    /// returns the nodes of the search and its helpers, helper nodes are published periodically
+    fn total_nodes(&self) -> usize {
+        self.nodes
+            + self
+                .helper_node_counters
+                .iter()
+                .map(|counter| counter.load(Ordering::Relaxed))
+                .sum::<usize>()
+    }
+
+    /// tells whether the search has to stop, checks the stop flag and the limits
+    ///
+    /// the node limit counts the nodes of the helpers too
+    fn should_stop(&mut self) -> bool {
+        if self.stop.load(Ordering::Relaxed)
+            || (self.limits.nodes > 0 && self.nodes >= self.limits.nodes)
+        {
+            self.stopped = true;
+        } else if self.nodes & (TIME_CHECK_INTERVAL - 1) == 0 {
+            self.node_counter.store(self.nodes, Ordering::Relaxed);
+            if (self.limits.nodes > 0 && self.total_nodes() >= self.limits.nodes)
+                || self.time_manager.hard_limit_reached(self.elapsed())
+            {
+                self.stopped = true;
+            }
+        }
+        self.stopped
+    }
+
+    /// tells whether the position is drawn by the fifty move rule, repetition or insufficient material
+    fn is_draw(&self, state: &State) -> bool {
+        if state.halfmove_clock() >= FIFTY_MOVE_RULE_PLIES || state.is_insufficient_material() {
+            return true;
+        }
+        let last = self.hashes.len() - 1;
+        let back = std::cmp::min(state.halfmove_clock(), last);
+        let mut count = 1;
+        let mut ply = 2;
+        while ply <= back {
+            if self.hashes[last - ply] == self.hashes[last] {
+                count += 1;
+                if count >= SEARCH_REPETITION_COUNT {
+                    return true;
+                }
+            }
+            ply += 2;
+        }
+        false
+    }
+
+    /// extends a pv cut short by transposition table cutoffs with the best moves stored in the table,
+    /// up to depth moves
+    fn extend_pv_from_tt(&self, state: &mut State, pv: &mut Vec<Move>, depth: usize) {
+        let mut undos: Vec<UndoInfo> = Vec::new();
+        for mv in pv.iter() {
+            undos.push(state.make_move_with_undo(*mv));
+        }
+        while pv.len() < depth {
+            let mv = match self.tt.probe(state.hash()) {
+                Some(entry) if entry.mv != 0 => entry.mv,
+                _ => break,
+            };
+            let col = state.turn;
+            if !state.is_pseudo_legal(mv) {
+                break;
+            }
+            let undo = state.make_move_with_undo(mv);
+            if !state.is_legal_for_mover(col) {
+                state.unmake_move(&undo);
+                break;
+            }
+            undos.push(undo);
+            pv.push(mv);
+        }
+        while let Some(undo) = undos.pop() {
+            state.unmake_move(&undo);
+        }
+    }
+
+    /// copies the pv of the child into the pv of ply, preceded by move
+    fn update_pv(&mut self, ply: usize, mv: Move) {
+        self.pv[ply][ply] = mv;
+        let child_len = self.pv_len[ply + 1];
+        for i in ply + 1..child_len {
+            self.pv[ply][i] = self.pv[ply + 1][i];
+        }
+        self.pv_len[ply] = std::cmp::max(child_len, ply + 1);
+    }
+
+    /// tells whether null move pruning is safe in the state, it is not in atomic, where passing can
+    /// hide explosion threats, nor when the side to move has only pawns and king, where zugzwang is common
+    fn null_move_allowed(state: &State) -> bool {
+        if state.variant() == VARIANT_ATOMIC {
+            return false;
+        }
+        let col = state.turn;
+        let pieces = state.color_bitboard(col)
+            & !state.figure_bitboard(col, PAWN)
+            & !state.figure_bitboard(col, KING);
+        pieces != 0
+            || POCKET_FIGURES
+                .iter()
+                .any(|fig| *fig != PAWN && state.pocket_count(col, *fig) > 0)
+    }
+
+    /// returns the moves preceding ply on the search path, the last one first, 0 for none or a null move
+    fn previous_moves(&self, ply: usize) -> [Move; CONTINUATION_PLIES] {
+        let mut previous = [0; CONTINUATION_PLIES];
+        for (i, mv) in previous.iter_mut().enumerate() {
+            if ply > i {
+                *mv = self.moves[ply - 1 - i];
+            }
+        }
+        previous
+    }
+
+    /// tells whether the move at ply recaptures on the square where the opponent just captured
+    fn is_recapture(&self, ply: usize, mv: Move) -> bool {
+        if ply == 0 || !mv.is_capture() {
+            return false;
+        }
+        let last = self.moves[ply - 1];
+        last != 0 && last.is_capture() && last.to_sq() == mv.to_sq()
+    }
+
+    /// tells whether the move just made in the state is a sentry push that drives a piece out of the
+    /// area of the king of the side to move
+    fn exposes_king_by_push(state: &State, mv: Move) -> bool {
+        if !mv.is_push() {
+            return false;
+        }
+        let (ksq, ok) = state.king_square(state.turn);
+        ok && KING_AREA[ksq] & mv.to_sq().bitboard() != 0
+            && KING_AREA[ksq] & mv.push_sq().bitboard() == 0
+    }
+
+    /// returns the late move reduction of the move with index move index at depth, 0 for no reduction
+    fn late_move_reduction(depth: usize, move_index: usize) -> usize {
+        let reduction = 0.5 + (depth as f64).ln() * (move_index as f64).ln() / 2.25;
+        // the reduced search keeps at least depth 1
+        std::cmp::min(reduction as usize, depth - 2)
+    }
+
+    /// returns the negamax score of the state from the point of view of the side to move
+    ///
+    /// nodes with an open window are pv nodes, the others are searched with a null window and get
+    /// pruned and reduced more aggressively, allow null tells whether a null move may be tried
+    fn negamax(
+        &mut self,
+        state: &mut State,
+        depth: usize,
+        ply: usize,
+        mut alpha: Score,
+        beta: Score,
+        allow_null: bool,
+    ) -> Score {
+        if depth == 0 {
+            return self.quiescence(state, ply, alpha, beta);
+        }
+        self.pv_len[ply] = ply;
+        self.nodes += 1;
+        if self.should_stop() {
+            return DRAW_SCORE;
+        }
+        let col = state.turn;
+        if state.variant() == VARIANT_ATOMIC && !state.king_square(col).1 {
+            return -MATE_SCORE + ply as Score;
+        }
+        if ply > 0 && self.is_draw(state) {
+            return DRAW_SCORE;
+        }
+        if ply >= MAX_PLY - 1 {
+            return evaluate(state);
+        }
+        let hash = state.hash();
+        let excluded = self.excluded[ply];
+        let tt_entry = if excluded == 0 {
+            self.tt.probe(hash)
+        } else {
+            // the entry is the one of the search that excluded the move
+            None
+        };
+        let mut hash_move = 0;
+        if let Some(entry) = tt_entry {
+            hash_move = entry.mv;
+            if ply > 0 && entry.depth >= depth {
+                let score = score_from_tt(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => (),
+                }
+            }
+        }
+        let pv_node = beta - alpha > 1;
+        let in_check = state.is_in_check(col);
+        let static_eval = if in_check {
+            -INFINITE_SCORE
+        } else {
+            evaluate(state)
+        };
+        let prunable = !pv_node && !in_check && beta.abs() < MATE_BOUND;
+        if prunable
+            && self.options.reverse_futility
+            && depth <= REVERSE_FUTILITY_MAX_DEPTH
+            && static_eval - REVERSE_FUTILITY_MARGIN * depth as Score >= beta
+        {
+            return static_eval;
+        }
+        if prunable
+            && self.options.razoring
+            && depth <= RAZORING_MAX_DEPTH
+            && static_eval + RAZORING_MARGIN * depth as Score <= alpha
+        {
+            let score = self.quiescence(state, ply, alpha, alpha + 1);
+            if depth == 1 || score <= alpha {
+                return score;
+            }
+        }
+        if prunable
+            && self.options.null_move
+            && allow_null
+            && excluded == 0
+            && depth >= NULL_MOVE_MIN_DEPTH
+            && static_eval >= beta
+            && Search::null_move_allowed(state)
+        {
+            let reduction = NULL_MOVE_REDUCTION + depth / 6;
+            let undo = state.make_null_move_with_undo();
+            self.moves[ply] = 0;
+            self.hashes.push(state.hash());
+            let score = -self.negamax(
+                state,
+                depth.saturating_sub(1 + reduction),
+                ply + 1,
+                -beta,
+                -beta + 1,
+                false,
+            );
+            self.hashes.pop();
+            state.unmake_move(&undo);
+            if self.stopped {
+                return DRAW_SCORE;
+            }
+            if score >= beta {
+                // a null move mate does not prove a mate
+                return if score >= MATE_BOUND { beta } else { score };
+            }
+        }
+        let futility = prunable
+            && self.options.futility
+            && depth <= FUTILITY_MAX_DEPTH
+            && static_eval + FUTILITY_MARGIN * depth as Score <= alpha;
+        let can_extend = ply < 2 * self.root_depth;
+        let singular_move = match tt_entry {
+            Some(entry)
+                if can_extend
+                    && ply > 0
+                    && depth >= SINGULAR_MIN_DEPTH
+                    && entry.mv != 0
+                    && entry.bound != Bound::Upper
+                    && entry.depth + SINGULAR_TT_DEPTH_MARGIN >= depth
+                    && entry.score.abs() < MATE_BOUND =>
+            {
+                // the move is singular if all other moves fail low against a lowered bound
+                let singular_beta = entry.score - SINGULAR_MARGIN * depth as Score;
+                self.excluded[ply] = entry.mv;
+                let score = self.negamax(
+                    state,
+                    depth / 2,
+                    ply,
+                    singular_beta - 1,
+                    singular_beta,
+                    false,
+                );
+                self.excluded[ply] = 0;
+                if self.stopped {
+                    return DRAW_SCORE;
+                }
+                if score < singular_beta {
+                    entry.mv
+                } else {
+                    0
+                }
+            }
+            _ => 0,
+        };
+        let explosion_threat_before =
+            can_extend && state.variant() == VARIANT_ATOMIC && state.explosion_threats(col) != 0;
+        let original_alpha = alpha;
+        let previous_moves = self.previous_moves(ply);
+        let mut picker = MovePicker::new_for_search(
+            hash_move,
+            self.history.killers(ply),
+            self.history.counter_move(previous_moves[0]),
+            previous_moves,
+        );
+        let mut quiets_tried = [0; MAX_QUIETS_TRIED];
+        let mut num_quiets_tried = 0;
+        let mut best = -INFINITE_SCORE;
+        let mut best_move = 0;
+        let mut legal_moves = 0;
+        while let Some(mv) = picker.next_move_with_history(state, &self.history) {
+            if mv == excluded || (ply == 0 && self.root_excluded.contains(&mv)) {
+                continue;
+            }
+            let undo = state.make_move_with_undo(mv);
+            if !state.is_legal_for_mover(col) {
+                state.unmake_move(&undo);
+                continue;
+            }
+            legal_moves += 1;
+            let gives_check = state.is_in_check(state.turn);
+            let extension = if can_extend
+                && (gives_check
+                    || mv == singular_move
+                    || self.is_recapture(ply, mv)
+                    || Search::exposes_king_by_push(state, mv)
+                    || (state.variant() == VARIANT_ATOMIC
+                        && !explosion_threat_before
+                        && state.explosion_threats(col) != 0))
+            {
+                1
+            } else {
+                0
+            };
+            let quiet = !mv.is_capture() && !mv.is_promotion() && !mv.is_push();
+            // the first legal move is always searched, so best is set whenever a move is legal
+            let reducible = quiet && legal_moves > 1 && !in_check && !gives_check && extension == 0;
+            if futility && reducible {
+                state.unmake_move(&undo);
+                continue;
+            }
+            if quiet && num_quiets_tried < MAX_QUIETS_TRIED {
+                quiets_tried[num_quiets_tried] = mv;
+                num_quiets_tried += 1;
+            }
+            self.moves[ply] = mv;
+            self.hashes.push(state.hash());
+            let new_depth = depth - 1 + extension;
+            let score = if legal_moves == 1 {
+                -self.negamax(state, new_depth, ply + 1, -beta, -alpha, true)
+            } else {
+                // principal variation search, later moves only have to prove they are not better
+                let reduction = if self.options.lmr
+                    && reducible
+                    && depth >= LMR_MIN_DEPTH
+                    && legal_moves > LMR_MIN_MOVES
+                {
+                    Search::late_move_reduction(depth, legal_moves)
+                } else {
+                    0
+                };
+                let mut score = -self.negamax(
+                    state,
+                    new_depth - reduction,
+                    ply + 1,
+                    -alpha - 1,
+                    -alpha,
+                    true,
+                );
+                if score > alpha && reduction > 0 && !self.stopped {
+                    score = -self.negamax(state, new_depth, ply + 1, -alpha - 1, -alpha, true);
+                }
+                if score > alpha && score < beta && !self.stopped {
+                    score = -self.negamax(state, new_depth, ply + 1, -beta, -alpha, true);
+                }
+                score
+            };
+            self.hashes.pop();
+            state.unmake_move(&undo);
+            if self.stopped {
+                return DRAW_SCORE;
+            }
+            if score > best {
+                best = score;
+                if score > alpha {
+                    alpha = score;
+                    best_move = mv;
+                    self.update_pv(ply, mv);
+                    if alpha >= beta {
+                        if !mv.is_capture() && !mv.is_promotion() && !mv.is_push() {
+                            self.history.update(
+                                ply,
+                                depth,
+                                mv,
+                                &quiets_tried[..num_quiets_tried],
+                                &previous_moves,
+                            );
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+        if legal_moves == 0 {
+            if excluded != 0 {
+                // the excluded move is the only legal move, so it is singular
+                return alpha;
+            }
+            if ply == 0 && !self.root_excluded.is_empty() {
+                // all root moves are covered by earlier lines
+                return alpha;
+            }
+            if in_check {
+                return -MATE_SCORE + ply as Score;
+            }
+            return DRAW_SCORE;
+        }
+        if excluded != 0 || (ply == 0 && !self.root_excluded.is_empty()) {
+            // the score is not the one of the position, as moves were left out
+            return best;
+        }
+        let bound = if best >= beta {
+            Bound::Lower
+        } else if best > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.tt
+            .store(hash, best_move, score_to_tt(best, ply), depth, bound);
+        best
+    }
+
+    /// returns the quiescence score of the state, searching violent moves only, or all evasions when in check
+    ///
+    /// the side to move may stand pat on the static evaluation unless in check, violent moves that
+    /// cannot raise alpha even with a margin are pruned
+    fn quiescence(
+        &mut self,
+        state: &mut State,
+        ply: usize,
+        mut alpha: Score,
+        beta: Score,
+    ) -> Score {
+        self.pv_len[ply] = ply;
+        self.nodes += 1;
+        if self.should_stop() {
+            return DRAW_SCORE;
+        }
+        let col = state.turn;
+        if state.variant() == VARIANT_ATOMIC && !state.king_square(col).1 {
+            return -MATE_SCORE + ply as Score;
+        }
+        if self.is_draw(state) {
+            return DRAW_SCORE;
+        }
+        let in_check = state.is_in_check(col);
+        let stand_pat = evaluate(state);
+        if ply >= MAX_PLY - 1 {
+            return stand_pat;
+        }
+        let mut best = -INFINITE_SCORE;
+        if !in_check {
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            best = stand_pat;
+            if stand_pat > alpha {
+                alpha = stand_pat;
+            }
+        }
+        let mut picker = if in_check {
+            MovePicker::new(0, [0; NUM_KILLERS])
+        } else {
+            MovePicker::new_violent()
+        };
+        let mut legal_moves = 0;
+        while let Some(mv) = picker.next_move(state) {
+            if !in_check && stand_pat + capture_gain(state, mv) + DELTA_MARGIN <= alpha {
+                continue;
+            }
+            let undo = state.make_move_with_undo(mv);
+            if !state.is_legal_for_mover(col) {
+                state.unmake_move(&undo);
+                continue;
+            }
+            legal_moves += 1;
+            self.hashes.push(state.hash());
+            let score = -self.quiescence(state, ply + 1, -beta, -alpha);
+            self.hashes.pop();
+            state.unmake_move(&undo);
+            if self.stopped {
+                return DRAW_SCORE;
+            }
+            if score > best {
+                best = score;
+                if score > alpha {
+                    alpha = score;
+                    self.update_pv(ply, mv);
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+        if in_check && legal_moves == 0 {
+            return -MATE_SCORE + ply as Score;
+        }
+        best
+    }
+}
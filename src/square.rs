@@ -1,5 +1,6 @@
 use crate::bitboard::*;
 use crate::constants::*;
+use crate::piece::*;
 
 use once_cell::sync::Lazy;
 
@@ -16,24 +17,80 @@ pub type File = usize;
 /// Square type represents a square of a chess board as an unsigned int
 pub type Square = usize;
 
-/// PawnInfo struct records the possible move and ep related squares of pawns
-#[derive(Clone)]
+/// PawnInfo struct records the possible move and ep related squares of pawns as bitboards
+#[derive(Copy, Clone)]
 pub struct PawnInfo {
-    pub pushes: Vec<Square>,
-    pub captures: Vec<Square>,
+    /// push_one is the square one step ahead, empty on the last rank
+    pub push_one: Bitboard,
+    /// push_two is the square two steps ahead for pawns on their start rank, empty otherwise
+    pub push_two: Bitboard,
+    /// captures are the squares diagonally ahead
+    pub captures: Bitboard,
 }
 
+/// EMPTY_PAWN_INFO represents a pawn that can neither push nor capture
+pub const EMPTY_PAWN_INFO: PawnInfo = PawnInfo {
+    push_one: 0,
+    push_two: 0,
+    captures: 0,
+};
+
 /// Move type represents a chess move
-pub type Move = u32;
+///
+/// bits from low to high : from square, to square, drop figure, promotion figure ( also the new
+/// direction of a lancer ), push square, moving piece, captured piece, move kind
+pub type Move = u64;
+
+/// MoveKind type represents the kind of a move
+pub type MoveKind = usize;
 
 /// MoveTrait adds methods to Move
+///
+/// Move is a plain integer, so the methods take it by value
+#[allow(clippy::wrong_self_convention)]
 pub trait MoveTrait {
     /// returns a move from from square and to square
     fn ft(from_sq: Square, to_sq: Square) -> Move;
+    /// returns a move from from square and to square with promotion figure, also used for lancer reorientation
+    fn ftp(from_sq: Square, to_sq: Square, prom_fig: Figure) -> Move;
+    /// returns a sentry push move, the sentry moves from from square to to square, pushing the piece there to push square
+    fn ft_push(from_sq: Square, to_sq: Square, push_sq: Square) -> Move;
+    /// returns a drop move of figure to square
+    fn drop(fig: Figure, to_sq: Square) -> Move;
+    /// returns the move with moving piece, captured piece and kind set
+    fn with_info(self, moving_piece: Piece, captured_piece: Piece, kind: MoveKind) -> Move;
     /// returns from square of move
     fn from_sq(self) -> Square;
     /// returns to square of move
     fn to_sq(self) -> Square;
+    /// returns the dropped figure of move, NO_FIGURE if the move is not a drop
+    fn drop_figure(self) -> Figure;
+    /// tells whether the move is a drop
+    fn is_drop(self) -> bool;
+    /// returns the promotion figure of move, NO_FIGURE if the move is not a promotion or lancer move
+    fn promotion_figure(self) -> Figure;
+    /// returns the direction a moving lancer takes up as lancer figure, NO_FIGURE if the move is not a lancer move
+    fn lancer_direction(self) -> Figure;
+    /// tells whether the move is a sentry push
+    fn is_push(self) -> bool;
+    /// returns the square the pushed piece is moved to by a sentry push
+    fn push_sq(self) -> Square;
+    /// returns the moving piece, the dropped piece for drops, NO_PIECE if the move was not completed by a state
+    fn moving_piece(self) -> Piece;
+    /// returns the captured piece, NO_PIECE for non captures
+    fn captured_piece(self) -> Piece;
+    /// returns the kind of the move
+    fn kind(self) -> MoveKind;
+    /// tells whether the move carries moving piece, captured piece and kind
+    fn is_complete(self) -> bool;
+    /// tells whether the move captures a piece
+    fn is_capture(self) -> bool;
+    /// tells whether the move is castling
+    fn is_castle(self) -> bool;
+    /// tells whether the move is an en passant capture
+    fn is_en_passant(self) -> bool;
+    /// tells whether the move is a pawn promotion
+    fn is_promotion(self) -> bool;
     /// return uci representation of move
     fn uci(self) -> String;
 }
@@ -41,7 +98,32 @@ pub trait MoveTrait {
 impl MoveTrait for Move {
     /// returns a move from from square and to square
     fn ft(from_sq: Square, to_sq: Square) -> Move {
-        ((from_sq << FROM_SQ_SHIFT) + (to_sq << TO_SQ_SHIFT)) as u32
+        ((from_sq << FROM_SQ_SHIFT) + (to_sq << TO_SQ_SHIFT)) as Move
+    }
+    /// returns a move from from square and to square with promotion figure, also used for lancer reorientation
+    fn ftp(from_sq: Square, to_sq: Square, prom_fig: Figure) -> Move {
+        Move::ft(from_sq, to_sq) + ((prom_fig << PROMOTION_FIGURE_SHIFT) as Move)
+    }
+    /// returns a sentry push move, the sentry moves from from square to to square, pushing the piece there to push square
+    fn ft_push(from_sq: Square, to_sq: Square, push_sq: Square) -> Move {
+        Move::ft(from_sq, to_sq)
+            + ((push_sq << PUSH_SQ_SHIFT) as Move)
+            + ((MOVE_KIND_SENTRY_PUSH << MOVE_KIND_SHIFT) as Move)
+    }
+    /// returns a drop move of figure to square
+    fn drop(fig: Figure, to_sq: Square) -> Move {
+        ((to_sq << TO_SQ_SHIFT) + (fig << DROP_FIGURE_SHIFT) + (MOVE_KIND_DROP << MOVE_KIND_SHIFT))
+            as Move
+    }
+    /// returns the move with moving piece, captured piece and kind set
+    fn with_info(self, moving_piece: Piece, captured_piece: Piece, kind: MoveKind) -> Move {
+        let mask = (PIECE_MASK << MOVING_PIECE_SHIFT)
+            | (PIECE_MASK << CAPTURED_PIECE_SHIFT)
+            | (MOVE_KIND_MASK << MOVE_KIND_SHIFT);
+        (self & !mask)
+            | ((moving_piece << MOVING_PIECE_SHIFT) as Move)
+            | ((captured_piece << CAPTURED_PIECE_SHIFT) as Move)
+            | ((kind << MOVE_KIND_SHIFT) as Move)
     }
     /// returns from square of move
     fn from_sq(self) -> Square {
@@ -51,9 +133,83 @@ impl MoveTrait for Move {
     fn to_sq(self) -> Square {
         ((self >> TO_SQ_SHIFT) & SQUARE_MASK) as Square
     }
+    /// returns the dropped figure of move, NO_FIGURE if the move is not a drop
+    fn drop_figure(self) -> Figure {
+        ((self >> DROP_FIGURE_SHIFT) & FIGURE_MASK) as Figure
+    }
+    /// tells whether the move is a drop
+    fn is_drop(self) -> bool {
+        self.drop_figure() != NO_FIGURE
+    }
+    /// returns the promotion figure of move, NO_FIGURE if the move is not a promotion or lancer move
+    fn promotion_figure(self) -> Figure {
+        ((self >> PROMOTION_FIGURE_SHIFT) & FIGURE_MASK) as Figure
+    }
+    /// returns the direction a moving lancer takes up as lancer figure, NO_FIGURE if the move is not a lancer move
+    fn lancer_direction(self) -> Figure {
+        if self.moving_piece().figure().base_figure() == LANCER {
+            self.promotion_figure()
+        } else {
+            NO_FIGURE
+        }
+    }
+    /// tells whether the move is a sentry push
+    fn is_push(self) -> bool {
+        self.kind() == MOVE_KIND_SENTRY_PUSH
+    }
+    /// returns the square the pushed piece is moved to by a sentry push
+    fn push_sq(self) -> Square {
+        ((self >> PUSH_SQ_SHIFT) & SQUARE_MASK) as Square
+    }
+    /// returns the moving piece, the dropped piece for drops, NO_PIECE if the move was not completed by a state
+    fn moving_piece(self) -> Piece {
+        ((self >> MOVING_PIECE_SHIFT) & PIECE_MASK) as Piece
+    }
+    /// returns the captured piece, NO_PIECE for non captures
+    fn captured_piece(self) -> Piece {
+        ((self >> CAPTURED_PIECE_SHIFT) & PIECE_MASK) as Piece
+    }
+    /// returns the kind of the move
+    fn kind(self) -> MoveKind {
+        ((self >> MOVE_KIND_SHIFT) & MOVE_KIND_MASK) as MoveKind
+    }
+    /// tells whether the move carries moving piece, captured piece and kind
+    fn is_complete(self) -> bool {
+        self.moving_piece() != NO_PIECE
+    }
+    /// tells whether the move captures a piece
+    fn is_capture(self) -> bool {
+        self.captured_piece() != NO_PIECE
+    }
+    /// tells whether the move is castling
+    fn is_castle(self) -> bool {
+        self.kind() == MOVE_KIND_CASTLE
+    }
+    /// tells whether the move is an en passant capture
+    fn is_en_passant(self) -> bool {
+        self.kind() == MOVE_KIND_EN_PASSANT
+    }
+    /// tells whether the move is a pawn promotion
+    fn is_promotion(self) -> bool {
+        self.kind() == MOVE_KIND_PROMOTION
+    }
     /// return uci representation of move
     fn uci(self) -> String {
-        format!("{}{}", self.from_sq().uci(), self.to_sq().uci())
+        if self.is_drop() {
+            return format!(
+                "{}@{}",
+                FIGURE_SAN_LETTERS[self.drop_figure()],
+                self.to_sq().uci()
+            );
+        }
+        let mut buff = format!("{}{}", self.from_sq().uci(), self.to_sq().uci());
+        if self.promotion_figure() != NO_FIGURE {
+            buff += self.promotion_figure().symbol();
+        }
+        if self.is_push() {
+            buff += &self.push_sq().uci();
+        }
+        buff
     }
 }
 
@@ -84,6 +240,10 @@ pub trait DeltaBuffer {
     fn len(&self) -> usize;
     /// get gets a delta by index from the delta buffer
     fn get(&self, i: usize) -> &Delta;
+    /// is_empty tells whether the delta buffer holds no deltas
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 // DeltaBuffer trait adds methods to 1 buffer that holds deltas
@@ -94,7 +254,7 @@ impl DeltaBuffer for [Delta; 1] {
     }
     /// get gets a delta by index from the delta buffer
     fn get(&self, i: usize) -> &Delta {
-        return &self[i];
+        &self[i]
     }
 }
 
@@ -106,7 +266,7 @@ impl DeltaBuffer for [Delta; 4] {
     }
     /// get gets a delta by index from the delta buffer
     fn get(&self, i: usize) -> &Delta {
-        return &self[i];
+        &self[i]
     }
 }
 
@@ -118,7 +278,19 @@ impl DeltaBuffer for [Delta; 8] {
     }
     /// get gets a delta by index from the delta buffer
     fn get(&self, i: usize) -> &Delta {
-        return &self[i];
+        &self[i]
+    }
+}
+
+// DeltaBuffer trait adds methods to variable size buffer that holds deltas
+impl DeltaBuffer for Vec<Delta> {
+    /// len tells the length of the delta buffer
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+    /// get gets a delta by index from the delta buffer
+    fn get(&self, i: usize) -> &Delta {
+        &self[i]
     }
 }
 
@@ -194,7 +366,7 @@ impl SquareTrait for Square {
 
     /// returns an otherwise empty bitboard with the bit for this square set
     fn bitboard(self) -> Bitboard {
-        1 << (LAST_FILE - self.file()) + self.rank() * NUM_FILES
+        1 << ((LAST_FILE - self.file()) + self.rank() * NUM_FILES)
     }
 
     /// adds a delta to a square and returns the resulting square
@@ -204,112 +376,112 @@ impl SquareTrait for Square {
         let rank = self.rank();
         match delta {
             Delta::N => {
-                return if rank > ONE_BEFORE_LAST_RANK {
+                if rank > ONE_BEFORE_LAST_RANK {
                     (0, false)
                 } else {
                     (rank_file(rank + 1, file), true)
                 }
             }
             Delta::NE => {
-                return if rank > ONE_BEFORE_LAST_RANK || file > ONE_BEFORE_LAST_FILE {
+                if rank > ONE_BEFORE_LAST_RANK || file > ONE_BEFORE_LAST_FILE {
                     (0, false)
                 } else {
                     (rank_file(rank + 1, file + 1), true)
                 }
             }
             Delta::NNE => {
-                return if rank > TWO_BEFORE_LAST_RANK || file > ONE_BEFORE_LAST_FILE {
+                if rank > TWO_BEFORE_LAST_RANK || file > ONE_BEFORE_LAST_FILE {
                     (0, false)
                 } else {
                     (rank_file(rank + 2, file + 1), true)
                 }
             }
             Delta::NEE => {
-                return if rank > ONE_BEFORE_LAST_RANK || file > TWO_BEFORE_LAST_FILE {
+                if rank > ONE_BEFORE_LAST_RANK || file > TWO_BEFORE_LAST_FILE {
                     (0, false)
                 } else {
                     (rank_file(rank + 1, file + 2), true)
                 }
             }
             Delta::E => {
-                return if file > ONE_BEFORE_LAST_FILE {
+                if file > ONE_BEFORE_LAST_FILE {
                     (0, false)
                 } else {
                     (rank_file(rank, file + 1), true)
                 }
             }
             Delta::SE => {
-                return if rank < 1 || file > ONE_BEFORE_LAST_FILE {
+                if rank < 1 || file > ONE_BEFORE_LAST_FILE {
                     (0, false)
                 } else {
                     (rank_file(rank - 1, file + 1), true)
                 }
             }
             Delta::SEE => {
-                return if rank < 1 || file > TWO_BEFORE_LAST_FILE {
+                if rank < 1 || file > TWO_BEFORE_LAST_FILE {
                     (0, false)
                 } else {
                     (rank_file(rank - 1, file + 2), true)
                 }
             }
             Delta::SSE => {
-                return if rank < 2 || file > ONE_BEFORE_LAST_FILE {
+                if rank < 2 || file > ONE_BEFORE_LAST_FILE {
                     (0, false)
                 } else {
                     (rank_file(rank - 2, file + 1), true)
                 }
             }
             Delta::S => {
-                return if rank < 1 {
+                if rank < 1 {
                     (0, false)
                 } else {
                     (rank_file(rank - 1, file), true)
                 }
             }
             Delta::SW => {
-                return if rank < 1 || file < 1 {
+                if rank < 1 || file < 1 {
                     (0, false)
                 } else {
                     (rank_file(rank - 1, file - 1), true)
                 }
             }
             Delta::SSW => {
-                return if rank < 2 || file < 1 {
+                if rank < 2 || file < 1 {
                     (0, false)
                 } else {
                     (rank_file(rank - 2, file - 1), true)
                 }
             }
             Delta::SWW => {
-                return if rank < 1 || file < 2 {
+                if rank < 1 || file < 2 {
                     (0, false)
                 } else {
                     (rank_file(rank - 1, file - 2), true)
                 }
             }
             Delta::W => {
-                return if file < 1 {
+                if file < 1 {
                     (0, false)
                 } else {
                     (rank_file(rank, file - 1), true)
                 }
             }
             Delta::NW => {
-                return if rank > ONE_BEFORE_LAST_RANK || file < 1 {
+                if rank > ONE_BEFORE_LAST_RANK || file < 1 {
                     (0, false)
                 } else {
                     (rank_file(rank + 1, file - 1), true)
                 }
             }
             Delta::NWW => {
-                return if rank > ONE_BEFORE_LAST_RANK || file < 2 {
+                if rank > ONE_BEFORE_LAST_RANK || file < 2 {
                     (0, false)
                 } else {
                     (rank_file(rank + 1, file - 2), true)
                 }
             }
             Delta::NNW => {
-                return if rank > TWO_BEFORE_LAST_RANK || file < 1 {
+                if rank > TWO_BEFORE_LAST_RANK || file < 1 {
                     (0, false)
                 } else {
                     (rank_file(rank + 2, file - 1), true)
@@ -327,7 +499,7 @@ impl SquareTrait for Square {
         if sq.bitboard() & occup != 0 {
             return (0, false);
         }
-        return (sq, true);
+        (sq, true)
     }
 }
 
@@ -368,39 +540,32 @@ pub fn sliding_attack<T: DeltaBuffer>(sq: Square, deltas: &T, occup: Bitboard) -
 pub type AttackTable = [Bitboard; BOARD_AREA];
 
 /// PAWN_INFOS records PawnInfo for color and square
-pub static PAWN_INFOS: Lazy<Vec<Vec<PawnInfo>>> = Lazy::new(|| {
-    let mut cpis: Vec<Vec<PawnInfo>> = Vec::new();
+pub static PAWN_INFOS: Lazy<[[PawnInfo; BOARD_AREA]; 2]> = Lazy::new(|| {
+    let mut cpis = [[EMPTY_PAWN_INFO; BOARD_AREA]; 2];
     for col in BLACK..WHITE + 1 {
-        let mut spis: Vec<PawnInfo> = Vec::new();
-        for sq in 0..BOARD_AREA {
-            let mut pi: PawnInfo = PawnInfo {
-                pushes: Vec::new(),
-                captures: Vec::new(),
-            };
+        for (sq, pi) in cpis[col].iter_mut().enumerate() {
             let push_delta: &Delta = if col == WHITE { &Delta::N } else { &Delta::S };
             let (push_one_sq, ok) = sq.add_delta(push_delta);
             if ok {
-                pi.pushes.push(push_one_sq);
+                pi.push_one = push_one_sq.bitboard();
                 let (capt_left_sq, ok) = push_one_sq.add_delta(&Delta::W);
                 if ok {
-                    pi.captures.push(capt_left_sq);
+                    pi.captures |= capt_left_sq.bitboard();
                 }
                 let (capt_right_sq, ok) = push_one_sq.add_delta(&Delta::E);
                 if ok {
-                    pi.captures.push(capt_right_sq);
+                    pi.captures |= capt_right_sq.bitboard();
                 }
                 if sq.rank() == PAWN_START_RANKS[col] {
                     let (push_two_sq, ok) = push_one_sq.add_delta(push_delta);
                     if ok {
-                        pi.pushes.push(push_two_sq);
+                        pi.push_two = push_two_sq.bitboard();
                     } else {
                         panic!("illegal pawn start rank")
                     }
                 }
             }
-            spis.push(pi);
         }
-        cpis.push(spis);
     }
     cpis
 });
@@ -408,48 +573,48 @@ pub static PAWN_INFOS: Lazy<Vec<Vec<PawnInfo>>> = Lazy::new(|| {
 /// KNIGHT_ATTACK is the attack table of knight
 pub static KNIGHT_ATTACK: Lazy<AttackTable> = Lazy::new(|| {
     let mut at = EMPTY_ATTACK_TABLE;
-    for sq in 0..BOARD_AREA {
-        at[sq] = jump_attack(sq, &KNIGHT_DELTAS, 0);
+    for (sq, attack) in at.iter_mut().enumerate() {
+        *attack = jump_attack(sq, &KNIGHT_DELTAS, 0);
     }
     at
 });
 /// BISHOP_ATTACK is the attack table of bishop
 pub static BISHOP_ATTACK: Lazy<AttackTable> = Lazy::new(|| {
     let mut at = EMPTY_ATTACK_TABLE;
-    for sq in 0..BOARD_AREA {
-        at[sq] = sliding_attack(sq, &BISHOP_DELTAS, 0);
+    for (sq, attack) in at.iter_mut().enumerate() {
+        *attack = sliding_attack(sq, &BISHOP_DELTAS, 0);
     }
     at
 });
 /// BISHOP_MAGIC_ATTACK is the magic attack table of bishop
 pub static BISHOP_MAGIC_ATTACK: Lazy<AttackTable> = Lazy::new(|| {
     let mut at = EMPTY_ATTACK_TABLE;
-    for sq in 0..BOARD_AREA {
-        at[sq] = magic_attack(sq, sliding_attack(sq, &BISHOP_DELTAS, 0));
+    for (sq, attack) in at.iter_mut().enumerate() {
+        *attack = magic_attack(sq, sliding_attack(sq, &BISHOP_DELTAS, 0));
     }
     at
 });
 /// ROOK_ATTACK is the attack table of rook
 pub static ROOK_ATTACK: Lazy<AttackTable> = Lazy::new(|| {
     let mut at = EMPTY_ATTACK_TABLE;
-    for sq in 0..BOARD_AREA {
-        at[sq] = sliding_attack(sq, &ROOK_DELTAS, 0);
+    for (sq, attack) in at.iter_mut().enumerate() {
+        *attack = sliding_attack(sq, &ROOK_DELTAS, 0);
     }
     at
 });
 /// ROOK_MAGIC_ATTACK is the magic attack table of rook
 pub static ROOK_MAGIC_ATTACK: Lazy<AttackTable> = Lazy::new(|| {
     let mut at = EMPTY_ATTACK_TABLE;
-    for sq in 0..BOARD_AREA {
-        at[sq] = magic_attack(sq, sliding_attack(sq, &ROOK_DELTAS, 0));
+    for (sq, attack) in at.iter_mut().enumerate() {
+        *attack = magic_attack(sq, sliding_attack(sq, &ROOK_DELTAS, 0));
     }
     at
 });
 /// QUEEN_ATTACK is the attack table of queen
 pub static QUEEN_ATTACK: Lazy<AttackTable> = Lazy::new(|| {
     let mut at = EMPTY_ATTACK_TABLE;
-    for sq in 0..BOARD_AREA {
-        at[sq] = sliding_attack(sq, &QUEEN_DELTAS, 0);
+    for (sq, attack) in at.iter_mut().enumerate() {
+        *attack = sliding_attack(sq, &QUEEN_DELTAS, 0);
     }
     at
 });
@@ -465,9 +630,9 @@ pub static LANCER_ATTACKS: Lazy<[AttackTable; NUM_LANCERS]> = Lazy::new(|| {
         EMPTY_ATTACK_TABLE,
         EMPTY_ATTACK_TABLE,
     ];
-    for ld in 0..NUM_LANCERS {
-        for sq in 0..BOARD_AREA {
-            ats[ld][sq] = sliding_attack(sq, &[LANCER_DELTAS[ld]], 0);
+    for (at, delta) in ats.iter_mut().zip(LANCER_DELTAS.iter()) {
+        for (sq, attack) in at.iter_mut().enumerate() {
+            *attack = sliding_attack(sq, &[*delta], 0);
         }
     }
     ats
@@ -475,16 +640,16 @@ pub static LANCER_ATTACKS: Lazy<[AttackTable; NUM_LANCERS]> = Lazy::new(|| {
 /// KING_ATTACK is the attack table of king
 pub static KING_ATTACK: Lazy<AttackTable> = Lazy::new(|| {
     let mut at = EMPTY_ATTACK_TABLE;
-    for sq in 0..BOARD_AREA {
-        at[sq] = jump_attack(sq, &QUEEN_DELTAS, 0);
+    for (sq, attack) in at.iter_mut().enumerate() {
+        *attack = jump_attack(sq, &QUEEN_DELTAS, 0);
     }
     at
 });
 /// KING_AREA is the attack table of king plus king square
 pub static KING_AREA: Lazy<AttackTable> = Lazy::new(|| {
     let mut at = EMPTY_ATTACK_TABLE;
-    for sq in 0..BOARD_AREA {
-        at[sq] = jump_attack(sq, &QUEEN_DELTAS, 0) | sq.bitboard();
+    for (sq, attack) in at.iter_mut().enumerate() {
+        *attack = jump_attack(sq, &QUEEN_DELTAS, 0) | sq.bitboard();
     }
     at
 });
@@ -552,7 +717,7 @@ pub fn find_magic_for_shift(shift: usize, mobility: Bitboard, max_tries: usize)
             return (magic, true);
         }
     }
-    return (0, false);
+    (0, false)
 }
 
 /// tries to find magic and shift in certain number of tries per shift for a mobility, starting from a maximum shift, going to minimum shift, returns a tuple of the found magic and a bool indicating success
@@ -577,7 +742,7 @@ pub fn find_magic_and_shift(
     if has_magic {
         return (last_good_magic, last_good_shift, true);
     }
-    return (0, 0, false);
+    (0, 0, false)
 }
 
 /// return magic attack for an attack
@@ -664,12 +829,7 @@ pub struct MagicInfo {
 
 /// returns the total number of magic look up table items
 pub fn total_magic_space(magics: [MagicInfo; BOARD_AREA]) -> usize {
-    let mut total = 0;
-    for i in 0..BOARD_AREA {
-        let mi = &magics[i];
-        total += 1 << mi.shift;
-    }
-    total
+    magics.iter().map(|mi| 1 << mi.shift).sum()
 }
 
 /// create magic lookup table
@@ -732,7 +892,7 @@ pub fn get_sliding_mobility(
     occup_them: Bitboard,
     mis: &[MagicInfo; BOARD_AREA],
     at: &AttackTable,
-    lookup_table: &Vec<Vec<Bitboard>>,
+    lookup_table: &[Vec<Bitboard>],
 ) -> Bitboard {
     let magic = mis[sq].magic;
     let shift = mis[sq].shift;
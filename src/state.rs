@@ -1,7 +1,10 @@
+use crate::betza::*;
 use crate::bitboard::*;
 use crate::constants::*;
+use crate::movelist::*;
 use crate::piece::*;
 use crate::square::*;
+use crate::zobrist::*;
 
 /// MoveBuffItem stores a move with meta information
 #[derive(Clone)]
@@ -11,23 +14,101 @@ pub struct MoveBuffItem {
 }
 
 /// CastlingRight represents a castling right
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct CastlingRight {
     pub can_castle: bool,
 }
 
 /// ColorCastlingRights represents castling rights for a color
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct ColorCastlingRights {
     pub rights: [CastlingRight; 2],
 }
 
 /// CastlingRights represents all castling rights
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct CastlingRigths {
     rights: [ColorCastlingRights; 2],
 }
 
+/// CastlingInfo records the squares involved in castling to one side
+pub struct CastlingInfo {
+    pub king_to_sq: Square,
+    pub partner_from_sq: Square,
+    pub partner_to_sq: Square,
+    /// empty records the squares that have to be empty between king and partner
+    pub empty: Bitboard,
+    /// safe records the squares that the king passes, including its start square, that must not be attacked
+    pub safe: Bitboard,
+}
+
+/// UndoInfo records what make move changed, so that unmake move can restore the state exactly
+#[derive(Copy, Clone)]
+pub struct UndoInfo {
+    mv: Move,
+    turn: Color,
+    ep_square: Square,
+    halfmove_clock: usize,
+    fullmove_number: usize,
+    has_disabled_move: bool,
+    disable_from_sq: Square,
+    disable_to_sq: Square,
+    castling_rights: CastlingRigths,
+    promoted: Bitboard,
+    hash: u64,
+    squares: [Square; MAX_UNDO_SQUARES],
+    pieces: [Piece; MAX_UNDO_SQUARES],
+    num_squares: usize,
+}
+
+/// GameResult tells whether and how a game has ended
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GameResult {
+    Ongoing,
+    Checkmate { winner: Color },
+    KingExploded { winner: Color },
+    Stalemate,
+    FiftyMoveRule,
+    InsufficientMaterial,
+    ThreefoldRepetition,
+}
+
+/// GameResult implementation
+impl GameResult {
+    /// tells whether the game is over
+    pub fn is_over(&self) -> bool {
+        *self != GameResult::Ongoing
+    }
+
+    /// returns the score string of the result, "1-0", "0-1", "1/2-1/2" or "*"
+    pub fn score(&self) -> &'static str {
+        match self {
+            GameResult::Ongoing => "*",
+            GameResult::Checkmate { winner } | GameResult::KingExploded { winner } => {
+                if *winner == WHITE {
+                    "1-0"
+                } else {
+                    "0-1"
+                }
+            }
+            _ => "1/2-1/2",
+        }
+    }
+
+    /// returns the reason of the result
+    pub fn reason(&self) -> &'static str {
+        match self {
+            GameResult::Ongoing => "ongoing",
+            GameResult::Checkmate { .. } => "checkmate",
+            GameResult::KingExploded { .. } => "king exploded",
+            GameResult::Stalemate => "stalemate",
+            GameResult::FiftyMoveRule => "fifty move rule",
+            GameResult::InsufficientMaterial => "insufficient material",
+            GameResult::ThreefoldRepetition => "threefold repetition",
+        }
+    }
+}
+
 /// State records the state of a chess game
 #[derive(Clone)]
 pub struct State {
@@ -43,13 +124,16 @@ pub struct State {
     by_figure: [[Bitboard; FIGURE_ARRAY_SIZE]; 2],
     by_color: [Bitboard; 2],
     castling_rights: CastlingRigths,
+    pockets: [[usize; FIGURE_ARRAY_SIZE]; 2],
+    promoted: Bitboard,
+    hash: u64,
     pub move_buff: Vec<MoveBuffItem>,
 }
 
 /// Variant type records the index of the variant
 pub type Variant = usize;
 
-trait VariantTrait {
+pub trait VariantTrait {
     /// returns name of variant
     fn string(self) -> String;
 }
@@ -61,22 +145,38 @@ impl VariantTrait for Variant {
             VARIANT_STANDARD => "Standard",
             VARIANT_EIGHTPIECE => "Eightpiece",
             VARIANT_ATOMIC => "Atomic",
+            VARIANT_BUGHOUSE => "Bughouse",
+            VARIANT_PLACEMENT => "Placement",
             _ => "Unknownvariant",
         };
         name.to_string()
     }
 }
 
+/// returns the variant for a variant name, case insensitive
+pub fn variant_from_name(name: &str) -> Option<Variant> {
+    (0..NUM_VARIANTS)
+        .find(|&variant| VARIANT_INFOS[variant].display_name.to_lowercase() == name.to_lowercase())
+}
+
 /// VariantInfo records variant information
 pub struct VariantInfo {
     pub start_fen: &'static str,
     pub display_name: &'static str,
+    pub has_pockets: bool,
+}
+
+/// State defaults to a new empty State
+impl Default for State {
+    fn default() -> State {
+        State::new()
+    }
 }
 
 /// State implementation
 impl State {
     /// parses piece placement
-    pub fn parse_piece_placement(&mut self, fen: &str) {
+    pub fn parse_piece_placement(&mut self, fen: &str) -> Result<(), String> {
         self.by_figure = [EMTPY_FIGURE_BITBOARDS, EMTPY_FIGURE_BITBOARDS];
         self.by_color = [0, 0];
         let mut rank: Rank = 0;
@@ -87,7 +187,7 @@ impl State {
         for i in 0..fen.len() {
             let c = &fen[i..i + 1];
             if file > LAST_FILE && c != "/" && c != " " {
-                panic!("invalid piece placement file");
+                return Err(format!("invalid piece placement file in {}", fen));
             }
             let mut examine_c = true;
             let sq: Square = (LAST_RANK - rank) * NUM_FILES + file;
@@ -115,7 +215,7 @@ impl State {
                     lancer_index = 0;
                     examine_c = false;
                 } else {
-                    panic!("invalid lancer")
+                    return Err(format!("invalid lancer in {}", fen));
                 }
             } else if lancer_index == 2 {
                 if c == "e" {
@@ -156,14 +256,17 @@ impl State {
             }
             if lancer_index == 0 && examine_c {
                 if c == " " {
-                    return;
+                    return Ok(());
                 } else if c == "/" {
+                    if rank == LAST_RANK {
+                        return Err(format!("too many ranks in {}", fen));
+                    }
                     file = 0;
                     rank += 1;
-                } else if c >= "1" && c <= "8" {
+                } else if ("1"..="8").contains(&c) {
                     for _ in 0..c.parse().expect("should not happen") {
                         if file > LAST_FILE {
-                            panic!("invalid piece placement file");
+                            return Err(format!("invalid piece placement file in {}", fen));
                         }
                         self.remove((LAST_RANK - rank) * NUM_FILES + file);
                         file += 1;
@@ -174,11 +277,15 @@ impl State {
                         self.put(sq, p);
                         file += 1;
                     } else {
-                        panic!("invalid fen symbol")
+                        return Err(format!("invalid fen symbol {:?}", c));
                     }
                 }
             }
         }
+        if lancer_index != 0 {
+            return Err(format!("invalid lancer in {}", fen));
+        }
+        Ok(())
     }
 
     /// creates a new empty State
@@ -198,26 +305,176 @@ impl State {
             castling_rights: CastlingRigths {
                 rights: [EMPTY_COLOR_CASTLING_RIGHTS, EMPTY_COLOR_CASTLING_RIGHTS],
             },
+            pockets: [EMPTY_POCKET, EMPTY_POCKET],
+            promoted: 0,
+            hash: 0,
             move_buff: Vec::new(),
         }
     }
 
-    /// sets state from fen
-    pub fn set_from_fen(&mut self, fen: &str) {
-        let parts: Vec<&str> = fen.split(" ").collect();
+    /// parses pockets, a list of fen symbols of the pieces in hand
+    pub fn parse_pockets(&mut self, fen: &str) -> Result<(), String> {
+        self.pockets = [EMPTY_POCKET, EMPTY_POCKET];
+        for c in fen.chars() {
+            let p = fen_symbol_to_piece(&c.to_string());
+            if p == NO_PIECE || !POCKET_FIGURES.contains(&p.figure()) {
+                return Err(format!("invalid pocket symbol {:?}", c));
+            }
+            self.add_to_pocket(p);
+        }
+        Ok(())
+    }
+
+    /// reports pockets as a list of fen symbols of the pieces in hand
+    pub fn report_pockets(&self) -> String {
+        let mut buff = "".to_string();
+        for col in [WHITE, BLACK].iter() {
+            for fig in POCKET_FIGURES.iter() {
+                for _ in 0..self.pockets[*col][*fig] {
+                    buff += color_figure(*col, *fig).fen_symbol();
+                }
+            }
+        }
+        buff
+    }
+
+    /// adds a piece to the pocket of its color
+    pub fn add_to_pocket(&mut self, p: Piece) {
+        let count = self.pockets[p.color()][p.figure()];
+        self.hash ^= pocket_key(p, count) ^ pocket_key(p, count + 1);
+        self.pockets[p.color()][p.figure()] += 1;
+    }
+
+    /// removes a piece from the pocket of its color
+    pub fn remove_from_pocket(&mut self, p: Piece) {
+        let count = self.pockets[p.color()][p.figure()];
+        if count > 0 {
+            self.hash ^= pocket_key(p, count) ^ pocket_key(p, count - 1);
+            self.pockets[p.color()][p.figure()] -= 1;
+        }
+    }
+
+    /// returns the number of pieces in the pocket of a color for a figure
+    pub fn pocket_count(&self, col: Color, fig: Figure) -> usize {
+        self.pockets[col][fig]
+    }
+
+    /// returns the number of pieces in the pocket of color
+    pub fn pocket_size(&self, col: Color) -> usize {
+        POCKET_FIGURES
+            .iter()
+            .map(|fig| self.pockets[col][*fig])
+            .sum()
+    }
+
+    /// tells whether the variant of the state has pockets
+    pub fn has_pockets(&self) -> bool {
+        VARIANT_INFOS[self.variant].has_pockets
+    }
+
+    /// tells whether the state is in the setup phase of Placement, where pieces are dropped onto the first rank
+    pub fn in_setup_phase(&self) -> bool {
+        self.variant == VARIANT_PLACEMENT && self.pockets != [EMPTY_POCKET, EMPTY_POCKET]
+    }
+
+    /// tells whether a placement drop of figure to square leaves room for the bishops of color on opposite colors
+    pub fn placement_drop_ok(&self, col: Color, fig: Figure, sq: Square) -> bool {
+        let bb = sq.bitboard();
+        let free = BACK_RANKS[col] & !(self.by_color[WHITE] | self.by_color[BLACK]) & !bb;
+        let mut bishops_left = self.pockets[col][BISHOP];
+        let mut placed = self.by_figure[col][BISHOP] & BACK_RANKS[col];
+        if fig == BISHOP {
+            bishops_left -= 1;
+            placed |= bb;
+        }
+        let on_dark = placed & BITBOARD_DARK_SQUARES != 0;
+        let on_light = placed & BITBOARD_LIGHT_SQUARES != 0;
+        if placed.count_ones() > 1 && !(on_dark && on_light) {
+            return false;
+        }
+        match bishops_left {
+            0 => true,
+            1 => {
+                (on_dark || free & BITBOARD_DARK_SQUARES != 0)
+                    && (on_light || free & BITBOARD_LIGHT_SQUARES != 0)
+            }
+            _ => free & BITBOARD_DARK_SQUARES != 0 && free & BITBOARD_LIGHT_SQUARES != 0,
+        }
+    }
+
+    /// sets castling rights from king and rook placement, used when the setup phase is over
+    pub fn set_castling_rights_from_placement(&mut self) {
+        for col in BLACK..WHITE + 1 {
+            let king_sq = if col == WHITE { SQUARE_E1 } else { SQUARE_E8 };
+            let rook_sqs = if col == WHITE {
+                [SQUARE_H1, SQUARE_A1]
+            } else {
+                [SQUARE_H8, SQUARE_A8]
+            };
+            let has_king = self.piece_at_square(king_sq) == color_figure(col, KING);
+            for (side, rook_sq) in rook_sqs.iter().enumerate() {
+                self.castling_rights.rights[col].rights[side].can_castle =
+                    has_king && self.piece_at_square(*rook_sq) == color_figure(col, ROOK);
+            }
+        }
+    }
+
+    /// sets state from fen, the state is left unchanged if the fen is invalid
+    pub fn set_from_fen(&mut self, fen: &str) -> Result<(), String> {
+        self.init_from_fen(self.variant, fen)
+    }
+
+    /// parses the fields of a fen into a new state, on error the state is left half set
+    fn parse_fen(&mut self, fen: &str) -> Result<(), String> {
+        if !fen.is_ascii() {
+            return Err(format!("invalid fen {:?}", fen));
+        }
+
+        let parts: Vec<&str> = fen.split_whitespace().collect();
 
         let l = parts.len();
 
         if l != 4 && l != 6 && l != 7 {
-            panic!("invalid number of fen fields {}", l);
+            return Err(format!("invalid number of fen fields {}", l));
         }
 
-        self.parse_piece_placement(parts[0]);
+        let mut placement = parts[0];
+
+        self.pockets = [EMPTY_POCKET, EMPTY_POCKET];
+
+        if let Some(index) = placement.find('[') {
+            // pockets in brackets, as in rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[Qp]
+            let pockets = placement[index + 1..].trim_end_matches(']');
+            self.parse_pockets(pockets)?;
+            placement = &placement[0..index];
+        } else if placement.matches('/').count() > LAST_RANK {
+            // pockets as ninth rank, as in rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR/Qp
+            let index = placement.rfind('/').expect("should not happen");
+            self.parse_pockets(&placement[index + 1..])?;
+            placement = &placement[0..index];
+        }
+
+        self.parse_piece_placement(placement)?;
+
+        if self.has_pockets() {
+            for fig in CUSTOM_MIN..CUSTOM_MAX + 1 {
+                if self.by_figure[WHITE][fig] | self.by_figure[BLACK][fig] != 0 {
+                    // captured custom pieces could not be dropped
+                    return Err(format!(
+                        "custom piece {} not allowed in {}",
+                        fig.symbol(),
+                        self.variant.string()
+                    ));
+                }
+            }
+        }
+
+        self.promoted = 0;
 
         match parts[1] {
             "w" => self.turn = WHITE,
             "b" => self.turn = BLACK,
-            _ => panic!("invalid turn {}", parts[1]),
+            _ => return Err(format!("invalid turn {}", parts[1])),
         }
 
         self.castling_rights = CastlingRigths {
@@ -234,40 +491,66 @@ impl State {
                     "Q" => self.castling_rights.rights[WHITE].rights[QUEEN_SIDE].can_castle = true,
                     "k" => self.castling_rights.rights[BLACK].rights[KING_SIDE].can_castle = true,
                     "q" => self.castling_rights.rights[BLACK].rights[QUEEN_SIDE].can_castle = true,
-                    _ => panic!("invalid castling right {}", r),
+                    _ => return Err(format!("invalid castling right {}", r)),
                 }
             }
         }
 
+        if self.in_setup_phase() {
+            // placement drops alternate, so the side to move holds as many pieces as the opponent or one more
+            let mover = self.pocket_size(self.turn);
+            let other = self.pocket_size(self.turn.inverse());
+            if mover < other || mover > other + 1 {
+                return Err(format!(
+                    "invalid placement pockets {} , side to move has {} piece(s) , opponent has {}",
+                    parts[0], mover, other
+                ));
+            }
+        }
+
         self.ep_square = SQUARE_A1;
 
         if parts[3] != "-" {
-            self.ep_square = Square::from_uci(parts[3].to_string()).0;
+            let (ep_square, ok) = Square::from_uci(parts[3].to_string());
+            if !ok {
+                return Err(format!("invalid ep square {}", parts[3]));
+            }
+            self.ep_square = ep_square;
         }
 
-        self.halfmove_clock = parts[4].parse().expect("invalid halfmove clock");
-        self.fullmove_number = parts[5].parse().expect("invalid fullmove number");
+        // epd positions have no clocks
+        self.halfmove_clock = 0;
+        self.fullmove_number = 1;
+
+        if l > 4 {
+            self.halfmove_clock = parts[4]
+                .parse()
+                .map_err(|_| format!("invalid halfmove clock {}", parts[4]))?;
+            self.fullmove_number = parts[5]
+                .parse()
+                .map_err(|_| format!("invalid fullmove number {}", parts[5]))?;
+        }
 
         self.has_disabled_move = false;
 
-        if self.variant == VARIANT_EIGHTPIECE {
-            if parts.len() > 6 {
-                if parts[6] != "-" {
-                    if parts[6].len() != 4 {
-                        panic!("invalid disabled mvoe {:?}", parts[6]);
-                    }
-                    let df = Square::from_uci(parts[6][0..2].to_string());
-                    let dt = Square::from_uci(parts[6][2..4].to_string());
-                    if df.1 && dt.1 {
-                        self.disable_from_sq = df.0;
-                        self.disable_to_sq = dt.0;
-                    } else {
-                        panic!("invalid disabled move {}", parts[6]);
-                    }
-                    self.has_disabled_move = true;
-                }
+        if self.variant == VARIANT_EIGHTPIECE && parts.len() > 6 && parts[6] != "-" {
+            if parts[6].len() != 4 {
+                return Err(format!("invalid disabled move {}", parts[6]));
             }
+            let df = Square::from_uci(parts[6][0..2].to_string());
+            let dt = Square::from_uci(parts[6][2..4].to_string());
+            if df.1 && dt.1 {
+                self.disable_from_sq = df.0;
+                self.disable_to_sq = dt.0;
+            } else {
+                return Err(format!("invalid disabled move {}", parts[6]));
+            }
+            self.has_disabled_move = true;
         }
+
+        self.hash = self.compute_hash();
+
+        Ok(())
     }
 
     // puts a piece on a square
@@ -275,7 +558,9 @@ impl State {
         if p == NO_PIECE {
             return;
         }
+        self.remove(sq);
         self.rep[sq] = p;
+        self.hash ^= piece_key(p, sq);
         let bb = sq.bitboard();
         self.by_figure[p.color()][p.figure()] |= bb;
         self.by_color[p.color()] |= bb;
@@ -288,6 +573,7 @@ impl State {
             return;
         }
         self.rep[sq] = NO_PIECE;
+        self.hash ^= piece_key(p, sq);
         let bb = sq.bitboard();
         self.by_figure[p.color()][p.figure()] &= !bb;
         self.by_color[p.color()] &= !bb;
@@ -295,8 +581,94 @@ impl State {
 
     /// initializes state to variant
     pub fn init(&mut self, variant: Variant) {
-        self.variant = variant;
-        self.set_from_fen(VARIANT_INFOS[self.variant].start_fen);
+        self.init_from_fen(variant, VARIANT_INFOS[variant].start_fen)
+            .expect("invalid start fen");
+    }
+
+    /// initializes state to variant and fen, the state is left unchanged if the fen is invalid
+    pub fn init_from_fen(&mut self, variant: Variant, fen: &str) -> Result<(), String> {
+        let mut state = State::new();
+        state.variant = variant;
+        state.parse_fen(fen)?;
+        *self = state;
+        Ok(())
+    }
+
+    /// returns the variant of the state
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// returns the halfmove clock, the number of plies since the last irreversible move
+    pub fn halfmove_clock(&self) -> usize {
+        self.halfmove_clock
+    }
+
+    /// returns the zobrist hash of the position
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// returns the part of the hash that make move does not maintain piece by piece
+    ///
+    /// covers side to move, castling rights, ep square, disabled move and promoted pieces,
+    /// make move xors it out before and back in after changing these
+    fn extras_hash(&self) -> u64 {
+        let mut hash: u64 = 0;
+        if self.turn == WHITE {
+            hash ^= ZOBRIST.turn;
+        }
+        for col in BLACK..WHITE + 1 {
+            for side in KING_SIDE..QUEEN_SIDE + 1 {
+                if self.castling_rights.rights[col].rights[side].can_castle {
+                    hash ^= ZOBRIST.castling[col][side];
+                }
+            }
+        }
+        if self.ep_square != SQUARE_A1 {
+            hash ^= ZOBRIST.ep[self.ep_square];
+        }
+        if self.has_disabled_move {
+            hash ^=
+                ZOBRIST.disable_from[self.disable_from_sq] ^ ZOBRIST.disable_to[self.disable_to_sq];
+        }
+        let mut promoted = self.promoted;
+        loop {
+            let (sq, ok) = promoted.pop_square();
+            if ok {
+                hash ^= ZOBRIST.promoted[sq];
+            } else {
+                break;
+            }
+        }
+        hash
+    }
+
+    /// computes the zobrist hash of the position from scratch
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = self.extras_hash();
+        for sq in 0..BOARD_AREA {
+            let p = self.rep[sq];
+            if p != NO_PIECE {
+                hash ^= piece_key(p, sq);
+            }
+        }
+        for col in BLACK..WHITE + 1 {
+            for fig in 0..FIGURE_ARRAY_SIZE {
+                hash ^= pocket_key(color_figure(col, fig), self.pockets[col][fig]);
+            }
+        }
+        hash
+    }
+
+    /// returns the pieces of color as bitboard
+    pub fn color_bitboard(&self, col: Color) -> Bitboard {
+        self.by_color[col]
+    }
+
+    /// returns the pieces of color and figure as bitboard, lancer directions are separate figures
+    pub fn figure_bitboard(&self, col: Color, fig: Figure) -> Bitboard {
+        self.by_figure[col][fig]
     }
 
     /// returns the piece at a square
@@ -312,22 +684,24 @@ impl State {
             for file in 0..NUM_FILES {
                 let sq: Square = (LAST_RANK - rank) * NUM_FILES + file;
                 let p = self.piece_at_square(sq);
-                let mut should_flush = false;
                 if p == NO_PIECE {
                     acc += 1;
-                } else {
-                    should_flush = true;
-                    buff = format!("{}{}", buff, p.fen_symbol());
                 }
-                if acc > 0 && (should_flush || file == LAST_FILE) {
+                if acc > 0 && (p != NO_PIECE || file == LAST_FILE) {
                     buff = format!("{}{}", buff, acc);
                     acc = 0;
                 }
+                if p != NO_PIECE {
+                    buff = format!("{}{}", buff, p.fen_symbol());
+                }
                 if file == LAST_FILE && rank < LAST_RANK {
                     buff = format!("{}/", buff);
                 }
             }
         }
+        if self.has_pockets() {
+            buff = format!("{}[{}]", buff, self.report_pockets());
+        }
         buff = format!("{} {}", buff, self.turn.turn_fen());
         let mut cfen = "".to_string();
         if self.castling_rights.rights[WHITE].rights[KING_SIDE].can_castle {
@@ -342,7 +716,7 @@ impl State {
         if self.castling_rights.rights[BLACK].rights[QUEEN_SIDE].can_castle {
             cfen = format!("{}{}", cfen, "q")
         }
-        if cfen == "" {
+        if cfen.is_empty() {
             cfen = "-".to_string();
         }
         let mut epfen = "-".to_string();
@@ -377,68 +751,340 @@ impl State {
         }
     }
 
-    /// returns mobility of color figure at square
-    pub fn color_figure_mobility_at_square(
-        &self,
+    /// returns mobility of figure at square for given occupancies
+    pub fn figure_mobility(
         sq: Square,
         gen_mode: MoveGenMode,
-        col: Color,
         fig: Figure,
+        occup_us: Bitboard,
+        occup_them: Bitboard,
     ) -> Bitboard {
         match fig.base_figure() {
-            KNIGHT => knight_mobility(
-                sq,
-                gen_mode,
-                self.by_color[col],
-                self.by_color[col.inverse()],
-            ),
-            BISHOP => bishop_mobility(
-                sq,
-                gen_mode,
-                self.by_color[col],
-                self.by_color[col.inverse()],
-            ),
-            SENTRY => bishop_mobility(
-                sq,
-                gen_mode,
-                self.by_color[col],
-                self.by_color[col.inverse()],
-            ),
-            ROOK => rook_mobility(
-                sq,
-                gen_mode,
-                self.by_color[col],
-                self.by_color[col.inverse()],
-            ),
-            JAILER => jailer_mobility(
+            KNIGHT => knight_mobility(sq, gen_mode, occup_us, occup_them),
+            BISHOP => bishop_mobility(sq, gen_mode, occup_us, occup_them),
+            SENTRY => bishop_mobility(sq, gen_mode, occup_us, occup_them),
+            ROOK => rook_mobility(sq, gen_mode, occup_us, occup_them),
+            JAILER => jailer_mobility(sq, gen_mode, occup_us, occup_them),
+            QUEEN => queen_mobility(sq, gen_mode, occup_us, occup_them),
+            LANCER => lancer_mobility(
                 sq,
                 gen_mode,
-                self.by_color[col],
-                self.by_color[col.inverse()],
+                occup_us,
+                occup_them,
+                LANCER_ATTACKS[fig.lancer_direction()][sq],
             ),
-            QUEEN => queen_mobility(
+            KING => king_mobility(sq, gen_mode, occup_us, occup_them),
+            _ => 0,
+        }
+    }
+
+    /// returns mobility of color figure at square
+    pub fn color_figure_mobility_at_square(
+        &self,
+        sq: Square,
+        gen_mode: MoveGenMode,
+        col: Color,
+        fig: Figure,
+    ) -> Bitboard {
+        if fig >= CUSTOM_MIN {
+            // custom pieces may move differently for the two colors
+            return custom_figure_mobility(
+                fig,
                 sq,
                 gen_mode,
+                col,
                 self.by_color[col],
                 self.by_color[col.inverse()],
-            ),
+            );
+        }
+        State::figure_mobility(
+            sq,
+            gen_mode,
+            fig,
+            self.by_color[col],
+            self.by_color[col.inverse()],
+        )
+    }
+
+    /// returns the pieces of color that are jailed by an adjacent enemy jailer, kings cannot be jailed
+    pub fn jailed_for_color(&self, col: Color) -> Bitboard {
+        let mut jailers = self.by_figure[col.inverse()][JAILER];
+        let mut area: Bitboard = 0;
+        loop {
+            let (sq, ok) = jailers.pop_square();
+            if ok {
+                area |= KING_ATTACK[sq];
+            } else {
+                break;
+            }
+        }
+        area & self.by_color[col] & !self.by_figure[col][KING]
+    }
+
+    /// returns the squares attacked by the piece of color at square
+    fn piece_attacks(&self, sq: Square, col: Color, occup: Bitboard) -> Bitboard {
+        let fig = self.piece_at_square(sq).figure();
+        match fig.base_figure() {
+            PAWN => PAWN_INFOS[col][sq].captures,
+            KNIGHT => KNIGHT_ATTACK[sq],
+            BISHOP => bishop_mobility(sq, MoveGenMode::All, 0, occup),
+            ROOK => rook_mobility(sq, MoveGenMode::All, 0, occup),
+            QUEEN => queen_mobility(sq, MoveGenMode::All, 0, occup),
             LANCER => lancer_mobility(
                 sq,
-                gen_mode,
-                self.by_color[col],
+                MoveGenMode::All,
+                0,
                 self.by_color[col.inverse()],
                 LANCER_ATTACKS[fig.lancer_direction()][sq],
             ),
-            KING => king_mobility(
-                sq,
-                gen_mode,
-                self.by_color[col],
-                self.by_color[col.inverse()],
-            ),
+            KING => {
+                if self.variant == VARIANT_ATOMIC {
+                    0
+                } else {
+                    KING_ATTACK[sq]
+                }
+            }
+            CUSTOM_MIN..=CUSTOM_MAX => custom_figure_attack(fig, sq, col, occup),
             _ => 0,
         }
     }
 
+    /// returns the squares attacked by color, that is the squares where color could capture a piece
+    pub fn attacks_by_color(&self, col: Color) -> Bitboard {
+        let occup = self.by_color[WHITE] | self.by_color[BLACK];
+        let mut attacks: Bitboard = 0;
+        let mut bb = self.by_color[col] & !self.jailed_for_color(col);
+        loop {
+            let (sq, ok) = bb.pop_square();
+            if ok {
+                attacks |= self.piece_attacks(sq, col, occup);
+            } else {
+                break;
+            }
+        }
+        attacks
+    }
+
+    /// returns the pieces giving check to the king of color
+    pub fn checkers(&self, col: Color) -> Bitboard {
+        if !self.is_in_check(col) {
+            return 0;
+        }
+        let (ksq, _) = self.king_square(col);
+        let occup = self.by_color[WHITE] | self.by_color[BLACK];
+        let mut checkers: Bitboard = 0;
+        let mut bb = self.by_color[col.inverse()] & !self.jailed_for_color(col.inverse());
+        loop {
+            let (sq, ok) = bb.pop_square();
+            if ok {
+                if self.piece_attacks(sq, col.inverse(), occup) & ksq.bitboard() != 0 {
+                    checkers |= sq.bitboard();
+                }
+            } else {
+                break;
+            }
+        }
+        checkers
+    }
+
+    /// returns the pieces next to the king of the opponent of color that color attacks, in atomic capturing
+    /// any of them explodes that king, kings cannot capture in atomic and are not counted as attackers
+    pub fn explosion_threats(&self, col: Color) -> Bitboard {
+        let (ksq, ok) = self.king_square(col.inverse());
+        if !ok {
+            return 0;
+        }
+        let targets = KING_AREA[ksq] & self.by_color[col.inverse()] & !ksq.bitboard();
+        if targets == 0 {
+            return 0;
+        }
+        let occup = self.by_color[WHITE] | self.by_color[BLACK];
+        let mut attacks: Bitboard = 0;
+        let mut bb = self.by_color[col] & !self.by_figure[col][KING] & !self.jailed_for_color(col);
+        loop {
+            let (sq, ok) = bb.pop_square();
+            if ok {
+                attacks |= self.piece_attacks(sq, col, occup);
+            } else {
+                break;
+            }
+        }
+        attacks & targets
+    }
+
+    /// returns the king square of color together with a bool indicating whether color has a king
+    pub fn king_square(&self, col: Color) -> (Square, bool) {
+        let mut bb = self.by_figure[col][KING];
+        bb.pop_square()
+    }
+
+    /// tells whether the king of color is in check
+    pub fn is_in_check(&self, col: Color) -> bool {
+        let (ksq, ok) = self.king_square(col);
+        if !ok {
+            return false;
+        }
+        if self.variant == VARIANT_ATOMIC
+            && KING_ATTACK[ksq] & self.by_figure[col.inverse()][KING] != 0
+        {
+            // adjacent kings cannot be in check in atomic, capturing one would explode the other
+            return false;
+        }
+        self.attacks_by_color(col.inverse()) & ksq.bitboard() != 0
+    }
+
+    /// returns the promotion figures of the variant
+    pub fn promotion_figures(&self) -> &'static [Figure] {
+        if self.variant == VARIANT_EIGHTPIECE {
+            &EIGHTPIECE_PROMOTION_FIGURES
+        } else {
+            &PROMOTION_FIGURES
+        }
+    }
+
+    /// adds a pawn move, expanded to all promotions if the pawn reaches the last rank
+    fn add_pawn_move(&self, from_sq: Square, to_sq: Square, moves: &mut MoveList) {
+        if to_sq.bitboard() & (BITBOARD_RANK_1 | BITBOARD_RANK_8) != 0 {
+            for fig in self.promotion_figures().iter() {
+                moves.push(Move::ftp(from_sq, to_sq, *fig));
+            }
+        } else {
+            moves.push(Move::ft(from_sq, to_sq));
+        }
+    }
+
+    /// returns the push squares of a pawn of color at square, honouring blocking
+    fn pawn_pushes(&self, sq: Square, col: Color, occup: Bitboard) -> Bitboard {
+        let pi = &PAWN_INFOS[col][sq];
+        if pi.push_one & occup != 0 {
+            return 0;
+        }
+        pi.push_one | (pi.push_two & !occup)
+    }
+
+    /// generates pawn moves of color from square
+    fn generate_pawn_moves(
+        &self,
+        sq: Square,
+        col: Color,
+        gen_mode: MoveGenMode,
+        moves: &mut MoveList,
+    ) {
+        let occup = self.by_color[WHITE] | self.by_color[BLACK];
+        let promotion_ranks = BITBOARD_RANK_1 | BITBOARD_RANK_8;
+        let pushes = self.pawn_pushes(sq, col, occup);
+        let mut captures = PAWN_INFOS[col][sq].captures & self.by_color[col.inverse()];
+        if self.ep_square != SQUARE_A1 {
+            captures |= PAWN_INFOS[col][sq].captures & self.ep_square.bitboard() & !occup;
+        }
+        let mut targets = match gen_mode {
+            MoveGenMode::Violent => captures | (pushes & promotion_ranks),
+            MoveGenMode::Quiet => pushes & !promotion_ranks,
+            MoveGenMode::All => captures | pushes,
+        };
+        loop {
+            let (to_sq, ok) = targets.pop_square();
+            if ok {
+                self.add_pawn_move(sq, to_sq, moves);
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// tells whether the castling partner ( rook, or jailer in eightpiece ) of color stands on square
+    fn is_castling_partner(&self, sq: Square, col: Color) -> bool {
+        let p = self.piece_at_square(sq);
+        p != NO_PIECE && p.color() == col && (p.figure() == ROOK || p.figure() == JAILER)
+    }
+
+    /// generates castling moves of color
+    fn generate_castling_moves(&self, col: Color, moves: &mut MoveList) {
+        let king_sq = CASTLING_KING_SQUARES[col];
+        if self.piece_at_square(king_sq) != color_figure(col, KING) {
+            return;
+        }
+        let occup = self.by_color[WHITE] | self.by_color[BLACK];
+        let mut attacks: Option<Bitboard> = None;
+        for (side, ci) in CASTLING_INFOS[col].iter().enumerate() {
+            if !self.castling_rights.rights[col].rights[side].can_castle {
+                continue;
+            }
+            if !self.is_castling_partner(ci.partner_from_sq, col) || ci.empty & occup != 0 {
+                continue;
+            }
+            let attacks = *attacks.get_or_insert_with(|| self.attacks_by_color(col.inverse()));
+            if ci.safe & attacks != 0 {
+                continue;
+            }
+            moves.push(Move::ft(king_sq, ci.king_to_sq));
+        }
+    }
+
+    /// returns the squares a piece pushed by a sentry of color from square can be moved to
+    pub fn push_destinations(&self, sentry_sq: Square, pushed_sq: Square, col: Color) -> Bitboard {
+        let pushed = self.piece_at_square(pushed_sq);
+        let occup_us = (self.by_color[col] & !sentry_sq.bitboard()) | pushed_sq.bitboard();
+        let occup_them = self.by_color[col.inverse()] & !pushed_sq.bitboard();
+        let fig = pushed.figure();
+        let dests = match fig.base_figure() {
+            PAWN => {
+                (self.pawn_pushes(pushed_sq, col, occup_us | occup_them)
+                    | (PAWN_INFOS[col][pushed_sq].captures & occup_them))
+                    & !(BITBOARD_RANK_1 | BITBOARD_RANK_8)
+            }
+            KING => 0,
+            SENTRY => bishop_mobility(pushed_sq, MoveGenMode::Quiet, occup_us, occup_them),
+            CUSTOM_MIN..=CUSTOM_MAX => {
+                custom_figure_mobility(fig, pushed_sq, MoveGenMode::All, col, occup_us, occup_them)
+            }
+            _ => State::figure_mobility(pushed_sq, MoveGenMode::All, fig, occup_us, occup_them),
+        };
+        dests & !(self.by_figure[WHITE][KING] | self.by_figure[BLACK][KING])
+    }
+
+    /// generates sentry moves of color from square, a sentry cannot capture, instead it pushes enemy pieces
+    fn generate_sentry_moves(
+        &self,
+        sq: Square,
+        col: Color,
+        gen_mode: MoveGenMode,
+        moves: &mut MoveList,
+    ) {
+        if let MoveGenMode::Quiet | MoveGenMode::All = gen_mode {
+            let mut mob = self.color_figure_mobility_at_square(sq, MoveGenMode::Quiet, col, SENTRY);
+            loop {
+                let (to_sq, ok) = mob.pop_square();
+                if ok {
+                    moves.push(Move::ft(sq, to_sq));
+                } else {
+                    break;
+                }
+            }
+        }
+        if let MoveGenMode::Violent | MoveGenMode::All = gen_mode {
+            let mut targets =
+                self.color_figure_mobility_at_square(sq, MoveGenMode::Violent, col, SENTRY)
+                    & !self.by_figure[col.inverse()][KING];
+            loop {
+                let (to_sq, ok) = targets.pop_square();
+                if ok {
+                    let mut dests = self.push_destinations(sq, to_sq, col);
+                    loop {
+                        let (push_sq, ok) = dests.pop_square();
+                        if ok {
+                            moves.push(Move::ft_push(sq, to_sq, push_sq));
+                        } else {
+                            break;
+                        }
+                    }
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+
     /// generates pseudo legal moves for turn
     pub fn generate_pseudo_legal_moves(&mut self, gen_mode: MoveGenMode) -> Vec<Move> {
         self.generate_pseudo_legal_moves_for_color(gen_mode, self.turn)
@@ -450,61 +1096,330 @@ impl State {
         gen_mode: MoveGenMode,
         col: Color,
     ) -> Vec<Move> {
-        let mut moves: Vec<Move> = vec![0; 0];
-        let mut bb = self.by_color[col];
+        let mut moves = MoveList::new();
+        self.generate_moves_for_color(gen_mode, col, &mut moves);
+        moves.to_vec()
+    }
+
+    /// generates pseudo legal moves for color into a move list, the list is cleared first
+    pub fn generate_moves_for_color(
+        &self,
+        gen_mode: MoveGenMode,
+        col: Color,
+        moves: &mut MoveList,
+    ) {
+        moves.clear();
+        if self.in_setup_phase() {
+            match gen_mode {
+                MoveGenMode::Violent => (),
+                _ => self.generate_drops_for_color(col, moves),
+            }
+            self.complete_moves(moves);
+            return;
+        }
+        let mut bb = self.by_color[col] & !self.jailed_for_color(col);
         loop {
             let (sq, ok) = bb.pop_square();
             if ok {
-                let p = self.piece_at_square(sq);
-                let fig = p.figure();
-                match fig {
-                    PAWN => {
-                        let pi: &PawnInfo = &PAWN_INFOS[col][sq];
-                        let mut do_pawn_moves = |pushes, captures| {
-                            if pushes {
-                                for to_sq in pi.pushes.iter() {
-                                    if self.piece_at_square(*to_sq) == NO_PIECE {
-                                        moves.push(Move::ft(sq, *to_sq));
-                                    }
-                                }
-                            }
-                            if captures {
-                                for to_sq in pi.captures.iter() {
-                                    let cp: Piece = self.piece_at_square(*to_sq);
-                                    if cp != NO_PIECE && cp.color() == col.inverse() {
-                                        moves.push(Move::ft(sq, *to_sq));
-                                    }
-                                }
+                self.generate_moves_for_square(sq, gen_mode, col, moves);
+            } else {
+                break;
+            }
+        }
+        if self.has_pockets() {
+            match gen_mode {
+                MoveGenMode::Violent => (),
+                _ => self.generate_drops_for_color(col, moves),
+            }
+        }
+        if self.has_disabled_move {
+            let (dfrom, dto) = (self.disable_from_sq, self.disable_to_sq);
+            moves.retain(|mv| mv.is_drop() || mv.from_sq() != dfrom || mv.to_sq() != dto);
+        }
+        self.complete_moves(moves);
+    }
+
+    /// generates pseudo legal moves of the piece of color on square into a move list, without completing them
+    fn generate_moves_for_square(
+        &self,
+        sq: Square,
+        gen_mode: MoveGenMode,
+        col: Color,
+        moves: &mut MoveList,
+    ) {
+        let p = self.piece_at_square(sq);
+        let fig = p.figure();
+        match fig.base_figure() {
+            PAWN => self.generate_pawn_moves(sq, col, gen_mode, moves),
+            SENTRY => self.generate_sentry_moves(sq, col, gen_mode, moves),
+            _ => {
+                let king_gen_mode = match (fig, self.variant, gen_mode) {
+                    // the king cannot capture in atomic
+                    (KING, VARIANT_ATOMIC, MoveGenMode::Violent) => None,
+                    (KING, VARIANT_ATOMIC, _) => Some(MoveGenMode::Quiet),
+                    _ => Some(gen_mode),
+                };
+                let mut mob = match king_gen_mode {
+                    Some(gen_mode) => self.color_figure_mobility_at_square(sq, gen_mode, col, fig),
+                    None => 0,
+                };
+                loop {
+                    let (to_sq, ok) = mob.pop_square();
+                    if ok {
+                        if fig.base_figure() == LANCER {
+                            // a lancer reorients itself after moving
+                            for dir in 0..NUM_LANCERS {
+                                moves.push(Move::ftp(sq, to_sq, LANCER_MIN + dir));
                             }
-                        };
-                        match gen_mode {
-                            MoveGenMode::Violent => do_pawn_moves(false, true),
-                            MoveGenMode::Quiet => do_pawn_moves(true, false),
-                            MoveGenMode::All => do_pawn_moves(true, true),
+                        } else {
+                            moves.push(Move::ft(sq, to_sq));
                         }
+                    } else {
+                        break;
                     }
-                    _ => {
-                        let mut mob =
-                            self.color_figure_mobility_at_square(sq, gen_mode, col, p.figure());
-                        loop {
-                            let (to_sq, ok) = mob.pop_square();
-                            if ok {
-                                moves.push(Move::ft(sq, to_sq));
-                            } else {
-                                break;
-                            }
-                        }
+                }
+                if fig == KING {
+                    if let MoveGenMode::Quiet | MoveGenMode::All = gen_mode {
+                        self.generate_castling_moves(col, moves);
                     }
                 }
+            }
+        }
+    }
+
+    /// tells whether a complete move is pseudo legal in the state, used to verify moves from other positions
+    pub fn is_pseudo_legal(&self, mv: Move) -> bool {
+        if !mv.is_complete() || mv.moving_piece().color() != self.turn {
+            return false;
+        }
+        let mut moves = MoveList::new();
+        if mv.is_drop() {
+            if !self.has_pockets() || self.pockets[self.turn][mv.drop_figure()] == 0 {
+                return false;
+            }
+            self.generate_drops_for_color(self.turn, &mut moves);
+        } else {
+            let from_sq = mv.from_sq();
+            if self.in_setup_phase()
+                || self.piece_at_square(from_sq) != mv.moving_piece()
+                || self.jailed_for_color(self.turn) & from_sq.bitboard() != 0
+                || (self.has_disabled_move
+                    && from_sq == self.disable_from_sq
+                    && mv.to_sq() == self.disable_to_sq)
+            {
+                return false;
+            }
+            self.generate_moves_for_square(from_sq, MoveGenMode::All, self.turn, &mut moves);
+        }
+        self.complete_moves(&mut moves);
+        moves.contains(&mv)
+    }
+
+    /// returns the move completed with moving piece, captured piece and kind, a complete move is returned as is
+    pub fn complete_move(&self, mv: Move) -> Move {
+        if mv.is_complete() {
+            return mv;
+        }
+        if mv.is_drop() {
+            return mv.with_info(
+                color_figure(self.turn, mv.drop_figure()),
+                NO_PIECE,
+                MOVE_KIND_DROP,
+            );
+        }
+        let moving = self.piece_at_square(mv.from_sq());
+        if mv.is_push() {
+            return mv.with_info(moving, NO_PIECE, MOVE_KIND_SENTRY_PUSH);
+        }
+        if self.castling_side(mv).1 {
+            return mv.with_info(moving, NO_PIECE, MOVE_KIND_CASTLE);
+        }
+        let capture_sq = self.capture_square(mv);
+        let mut captured = self.piece_at_square(capture_sq);
+        if captured != NO_PIECE && captured.color() == moving.color() {
+            captured = NO_PIECE;
+        }
+        let kind = if capture_sq != mv.to_sq() {
+            MOVE_KIND_EN_PASSANT
+        } else if moving.figure() == PAWN && mv.promotion_figure() != NO_FIGURE {
+            MOVE_KIND_PROMOTION
+        } else {
+            MOVE_KIND_NORMAL
+        };
+        mv.with_info(moving, captured, kind)
+    }
+
+    /// completes a list of moves with moving piece, captured piece and kind
+    fn complete_moves(&self, moves: &mut [Move]) {
+        for mv in moves.iter_mut() {
+            *mv = self.complete_move(*mv);
+        }
+    }
+
+    /// tells whether the position is legal for color that has just moved
+    pub fn is_legal_for_mover(&self, col: Color) -> bool {
+        let has_king = self.by_figure[col][KING] != 0;
+        if self.variant == VARIANT_ATOMIC {
+            if !has_king {
+                return false;
+            }
+            if self.by_figure[col.inverse()][KING] == 0 {
+                // exploding the enemy king wins, even when in check
+                return true;
+            }
+        }
+        !self.is_in_check(col)
+    }
+
+    /// tells whether a pseudo legal move is legal
+    pub fn is_legal_move(&self, mv: Move) -> bool {
+        let mut state = self.clone();
+        state.move_buff = Vec::new();
+        state.make_move(mv);
+        state.is_legal_for_mover(self.turn)
+    }
+
+    /// tells whether a pseudo legal move is legal, making and unmaking it on the state itself
+    pub fn is_legal_move_in_place(&mut self, mv: Move) -> bool {
+        let col = self.turn;
+        let undo = self.make_move_with_undo(mv);
+        let legal = self.is_legal_for_mover(col);
+        self.unmake_move(&undo);
+        legal
+    }
+
+    /// parses a move in uci notation, the move has to be legal in this state
+    pub fn move_from_uci(&self, uci: &str) -> (Move, bool) {
+        for mv in self.generate_legal_moves(MoveGenMode::All) {
+            if mv.uci() == uci {
+                return (mv, true);
+            }
+        }
+        (0, false)
+    }
+
+    /// generates legal moves for turn
+    pub fn generate_legal_moves(&self, gen_mode: MoveGenMode) -> Vec<Move> {
+        let mut state = self.clone();
+        state.move_buff = Vec::new();
+        let mut moves = MoveList::new();
+        state.generate_legal_moves_into(gen_mode, &mut moves);
+        moves.to_vec()
+    }
+
+    /// generates legal moves for turn into a move list, checking legality with make and unmake on the state itself
+    pub fn generate_legal_moves_into(&mut self, gen_mode: MoveGenMode, moves: &mut MoveList) {
+        self.generate_moves_for_color(gen_mode, self.turn, moves);
+        moves.retain(|mv| self.is_legal_move_in_place(mv));
+    }
+
+    /// tells whether neither side can ever deliver mate
+    ///
+    /// sentries and jailers cannot capture, hence cannot give check, but they can
+    /// block squares around a king, so only bare kings plus such pieces count as dead
+    pub fn is_insufficient_material(&self) -> bool {
+        // in bughouse the partner can always feed new material
+        if self.variant == VARIANT_BUGHOUSE || self.pockets != [EMPTY_POCKET, EMPTY_POCKET] {
+            return false;
+        }
+        let mut majors: Bitboard = 0;
+        let mut knights: Bitboard = 0;
+        let mut bishops: Bitboard = 0;
+        let mut non_checking: Bitboard = 0;
+        for col in BLACK..WHITE + 1 {
+            majors |=
+                self.by_figure[col][PAWN] | self.by_figure[col][ROOK] | self.by_figure[col][QUEEN];
+            for fig in LANCER_MIN..LANCER_MAX + 1 {
+                majors |= self.by_figure[col][fig];
+            }
+            // custom pieces are assumed to be able to mate
+            for fig in CUSTOM_MIN..CUSTOM_MAX + 1 {
+                majors |= self.by_figure[col][fig];
+            }
+            knights |= self.by_figure[col][KNIGHT];
+            bishops |= self.by_figure[col][BISHOP];
+            non_checking |= self.by_figure[col][SENTRY] | self.by_figure[col][JAILER];
+        }
+        if majors != 0 {
+            return false;
+        }
+        let minors = knights | bishops;
+        if minors == 0 {
+            return true;
+        }
+        if non_checking != 0 {
+            return false;
+        }
+        if minors.count_ones() == 1 {
+            return true;
+        }
+        // bishops on squares of one color only, capturing them may explode a king in atomic
+        self.variant != VARIANT_ATOMIC
+            && knights == 0
+            && (bishops & BITBOARD_DARK_SQUARES == 0 || bishops & BITBOARD_LIGHT_SQUARES == 0)
+    }
+
+    /// returns the result of the game in this state
+    pub fn game_result(&self) -> GameResult {
+        if self.variant == VARIANT_ATOMIC && self.by_figure[self.turn][KING] == 0 {
+            return GameResult::KingExploded {
+                winner: self.turn.inverse(),
+            };
+        }
+        if self.generate_legal_moves(MoveGenMode::All).is_empty() {
+            if self.is_in_check(self.turn) {
+                return GameResult::Checkmate {
+                    winner: self.turn.inverse(),
+                };
+            }
+            return GameResult::Stalemate;
+        }
+        if self.halfmove_clock >= FIFTY_MOVE_RULE_PLIES {
+            return GameResult::FiftyMoveRule;
+        }
+        if self.is_insufficient_material() {
+            return GameResult::InsufficientMaterial;
+        }
+        GameResult::Ongoing
+    }
+
+    /// generates drop moves of pieces in the pocket of color
+    pub fn generate_drops_for_color(&self, col: Color, moves: &mut MoveList) {
+        let empty = !(self.by_color[WHITE] | self.by_color[BLACK]);
+        for fig in POCKET_FIGURES.iter() {
+            if self.pockets[col][*fig] == 0 {
+                continue;
+            }
+            let setup = self.in_setup_phase();
+            let mut bb = if setup {
+                empty & BACK_RANKS[col]
+            } else if *fig == PAWN {
+                empty & !(BITBOARD_RANK_1 | BITBOARD_RANK_8)
             } else {
-                break;
+                empty
+            };
+            loop {
+                let (sq, ok) = bb.pop_square();
+                if ok {
+                    if setup && !self.placement_drop_ok(col, *fig, sq) {
+                        continue;
+                    }
+                    moves.push(Move::drop(*fig, sq));
+                } else {
+                    break;
+                }
             }
         }
-        moves
     }
 
     /// returns the state as pretty printable string
     pub fn pretty_print_string(&mut self) -> String {
+        let result = self.game_result();
+        self.pretty_print_string_with_result(result)
+    }
+
+    /// returns the state as pretty printable string, reporting a result determined by the caller
+    pub fn pretty_print_string_with_result(&mut self, result: GameResult) -> String {
         let mut buff = "".to_string();
         for rank in 0..NUM_RANKS {
             for file in 0..NUM_FILES {
@@ -517,31 +1432,30 @@ impl State {
             }
         }
         buff = format!(
-            "{}\nvariant {} fen {}\n",
+            "{}\nvariant {} fen {}\nhash {:016x}\nresult {} {}\n",
             buff,
             self.variant.string(),
-            self.report_fen()
+            self.report_fen(),
+            self.hash,
+            result.score(),
+            result.reason()
         );
         format!("{}\n{}\n", buff, self.gen_move_buff())
     }
 
     /// generates moves with meta information
     pub fn gen_move_buff(&mut self) -> String {
-        let moves = self.generate_pseudo_legal_moves_for_color(MoveGenMode::All, self.turn);
+        let moves = self.generate_legal_moves(MoveGenMode::All);
         let mut move_buff = "".to_string();
         self.move_buff = Vec::new();
-        for i in 0..moves.len() {
-            let mv = moves[i];
-            self.move_buff.push(MoveBuffItem {
-                mv: mv,
-                uci: mv.uci(),
-            });
+        for mv in moves {
+            self.move_buff.push(MoveBuffItem { mv, uci: mv.uci() });
         }
         self.move_buff.sort_by(|a, b| a.uci.cmp(&b.uci));
         for i in 0..self.move_buff.len() {
             let fromp = self.piece_at_square(self.move_buff[i].mv.from_sq());
             let mut san_letter = fromp.san_letter();
-            if fromp.figure() == PAWN {
+            if fromp.figure() == PAWN || self.move_buff[i].mv.is_drop() {
                 san_letter = "";
             }
             let move_str = format!("{}. {}{}", i + 1, san_letter, self.move_buff[i].uci);
@@ -561,15 +1475,319 @@ impl State {
         VARIANT_INFOS[self.variant].start_fen
     }
 
-    /// makes a move
-    pub fn make_move(&mut self, mv: Move) {
+    /// tells whether the piece on square was promoted, promoted pieces go to the pocket as pawns
+    pub fn is_promoted(&self, sq: Square) -> bool {
+        self.promoted & sq.bitboard() != 0
+    }
+
+    /// returns the square of the piece captured by a move, the ep square is not the capture square for en passant
+    pub fn capture_square(&self, mv: Move) -> Square {
+        let to_sq = mv.to_sq();
+        if mv.is_complete() {
+            if mv.is_en_passant() {
+                return rank_file(mv.from_sq().rank(), to_sq.file());
+            }
+            return to_sq;
+        }
+        if !mv.is_drop()
+            && self.ep_square != SQUARE_A1
+            && to_sq == self.ep_square
+            && self.piece_at_square(mv.from_sq()).figure() == PAWN
+        {
+            return rank_file(mv.from_sq().rank(), to_sq.file());
+        }
+        to_sq
+    }
+
+    /// returns the piece captured by a move, NO_PIECE for non captures, drops and sentry pushes
+    pub fn captured_piece(&self, mv: Move) -> Piece {
+        if mv.is_complete() {
+            return mv.captured_piece();
+        }
+        if mv.is_drop() || mv.is_push() {
+            return NO_PIECE;
+        }
+        let p = self.piece_at_square(self.capture_square(mv));
+        if p != NO_PIECE && p.color() == self.turn {
+            // castling onto own piece is not possible, but be defensive
+            return NO_PIECE;
+        }
+        p
+    }
+
+    /// explodes all non pawn pieces around square and the piece on square, as captures do in atomic
+    fn explode(&mut self, sq: Square) {
+        let mut area = KING_AREA[sq];
+        loop {
+            let (esq, ok) = area.pop_square();
+            if ok {
+                let p = self.piece_at_square(esq);
+                if p != NO_PIECE && (esq == sq || p.figure() != PAWN) {
+                    self.remove(esq);
+                }
+            } else {
+                break;
+            }
+        }
+        self.promoted &= !KING_AREA[sq];
+    }
+
+    /// revokes castling rights whose king or partner has left its square
+    fn update_castling_rights(&mut self) {
+        for col in BLACK..WHITE + 1 {
+            let has_king =
+                self.piece_at_square(CASTLING_KING_SQUARES[col]) == color_figure(col, KING);
+            for (side, ci) in CASTLING_INFOS[col].iter().enumerate() {
+                if !has_king || !self.is_castling_partner(ci.partner_from_sq, col) {
+                    self.castling_rights.rights[col].rights[side].can_castle = false;
+                }
+            }
+        }
+    }
+
+    /// returns the castling side of a move and whether the move is castling
+    pub fn castling_side(&self, mv: Move) -> (usize, bool) {
+        if mv.is_drop() || mv.is_push() || (mv.is_complete() && !mv.is_castle()) {
+            return (KING_SIDE, false);
+        }
         let from_sq = mv.from_sq();
         let to_sq = mv.to_sq();
-        let fromp: Piece = self.piece_at_square(from_sq);
-        //let top: Piece = self.piece_at_square(to_sq);
-        self.remove(from_sq);
-        self.put(to_sq, fromp);
+        let fromp = self.piece_at_square(from_sq);
+        let col = fromp.color();
+        if fromp == NO_PIECE
+            || fromp.figure() != KING
+            || from_sq != CASTLING_KING_SQUARES[col]
+            || (to_sq.file() as isize - from_sq.file() as isize).abs() != 2
+        {
+            return (KING_SIDE, false);
+        }
+        if to_sq.file() > from_sq.file() {
+            (KING_SIDE, true)
+        } else {
+            (QUEEN_SIDE, true)
+        }
+    }
+
+    /// returns the squares where a move puts pieces of the mover, a checker elsewhere gives discovered check
+    pub fn landing_squares(&self, mv: Move) -> Bitboard {
+        let to_sq = mv.to_sq();
+        if mv.is_push() {
+            return to_sq.bitboard() | mv.push_sq().bitboard();
+        }
+        let (side, castling) = self.castling_side(mv);
+        if castling {
+            return to_sq.bitboard() | CASTLING_INFOS[self.turn][side].partner_to_sq.bitboard();
+        }
+        to_sq.bitboard()
+    }
+
+    /// returns the squares whose content a move may change
+    fn affected_squares(&self, mv: Move) -> Bitboard {
+        let to_sq = mv.to_sq();
+        if mv.is_drop() {
+            return to_sq.bitboard();
+        }
+        let mut bb = mv.from_sq().bitboard() | to_sq.bitboard();
+        if mv.is_push() {
+            return bb | mv.push_sq().bitboard();
+        }
+        let (side, castling) = self.castling_side(mv);
+        if castling {
+            let ci = &CASTLING_INFOS[self.turn][side];
+            return bb | ci.partner_from_sq.bitboard() | ci.partner_to_sq.bitboard();
+        }
+        let capture_sq = self.capture_square(mv);
+        bb |= capture_sq.bitboard();
+        if self.variant == VARIANT_ATOMIC && self.piece_at_square(capture_sq) != NO_PIECE {
+            bb |= KING_AREA[to_sq];
+        }
+        bb
+    }
 
+    /// makes a move and returns the undo information needed to unmake it
+    pub fn make_move_with_undo(&mut self, mv: Move) -> UndoInfo {
+        let mv = self.complete_move(mv);
+        let mut undo = self.undo_info(mv);
+        let mut affected = self.affected_squares(mv);
+        loop {
+            let (sq, ok) = affected.pop_square();
+            if ok {
+                undo.squares[undo.num_squares] = sq;
+                undo.pieces[undo.num_squares] = self.rep[sq];
+                undo.num_squares += 1;
+            } else {
+                break;
+            }
+        }
+        self.make_move(mv);
+        undo
+    }
+
+    /// passes the turn without moving, as null move pruning needs, and returns the undo information
+    /// needed to unmake it with unmake move, the ep square and the disabled move are cleared
+    pub fn make_null_move_with_undo(&mut self) -> UndoInfo {
+        let undo = self.undo_info(0);
+        self.hash ^= self.extras_hash();
+        self.ep_square = SQUARE_A1;
+        self.has_disabled_move = false;
+        self.halfmove_clock += 1;
+        if self.turn == BLACK {
+            self.fullmove_number += 1;
+        }
         self.turn = self.turn.inverse();
+        self.hash ^= self.extras_hash();
+        undo
+    }
+
+    /// returns undo information for move that restores everything but the squares
+    fn undo_info(&self, mv: Move) -> UndoInfo {
+        UndoInfo {
+            mv,
+            turn: self.turn,
+            ep_square: self.ep_square,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            has_disabled_move: self.has_disabled_move,
+            disable_from_sq: self.disable_from_sq,
+            disable_to_sq: self.disable_to_sq,
+            castling_rights: self.castling_rights,
+            promoted: self.promoted,
+            hash: self.hash,
+            squares: [SQUARE_A1; MAX_UNDO_SQUARES],
+            pieces: [NO_PIECE; MAX_UNDO_SQUARES],
+            num_squares: 0,
+        }
+    }
+
+    /// unmakes a move made by make move with undo
+    pub fn unmake_move(&mut self, undo: &UndoInfo) {
+        for i in 0..undo.num_squares {
+            let sq = undo.squares[i];
+            self.remove(sq);
+            self.put(sq, undo.pieces[i]);
+        }
+        if undo.mv.is_drop() {
+            self.add_to_pocket(color_figure(undo.turn, undo.mv.drop_figure()));
+        }
+        self.turn = undo.turn;
+        self.ep_square = undo.ep_square;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.has_disabled_move = undo.has_disabled_move;
+        self.disable_from_sq = undo.disable_from_sq;
+        self.disable_to_sq = undo.disable_to_sq;
+        self.castling_rights = undo.castling_rights;
+        self.promoted = undo.promoted;
+        self.hash = undo.hash;
+    }
+
+    /// makes a move
+    pub fn make_move(&mut self, mv: Move) {
+        let mv = self.complete_move(mv);
+        self.hash ^= self.extras_hash();
+        let col = self.turn;
+        let to_sq = mv.to_sq();
+        let mut reset_clock = false;
+        let mut ep_square = SQUARE_A1;
+        let mut has_disabled_move = false;
+
+        if mv.is_drop() {
+            let p = color_figure(col, mv.drop_figure());
+            self.remove_from_pocket(p);
+            self.put(to_sq, p);
+            // a drop can never be taken back, so it is irreversible like a pawn move
+            reset_clock = true;
+            if self.variant == VARIANT_PLACEMENT && !self.in_setup_phase() {
+                self.set_castling_rights_from_placement();
+            }
+        } else {
+            let from_sq = mv.from_sq();
+            let fromp: Piece = self.piece_at_square(from_sq);
+            let fig = fromp.figure();
+            let from_promoted = self.is_promoted(from_sq);
+            let (side, castling) = self.castling_side(mv);
+            if castling {
+                let ci = &CASTLING_INFOS[col][side];
+                let partner = self.piece_at_square(ci.partner_from_sq);
+                self.remove(from_sq);
+                self.remove(ci.partner_from_sq);
+                self.put(to_sq, fromp);
+                self.put(ci.partner_to_sq, partner);
+            } else if mv.is_push() {
+                let push_sq = mv.push_sq();
+                let pushed = self.piece_at_square(to_sq);
+                let pushed_promoted = self.is_promoted(to_sq);
+                reset_clock = self.piece_at_square(push_sq) != NO_PIECE;
+                self.promoted &= !(from_sq.bitboard() | to_sq.bitboard() | push_sq.bitboard());
+                self.remove(from_sq);
+                self.remove(to_sq);
+                self.remove(push_sq);
+                self.put(to_sq, fromp);
+                self.put(push_sq, pushed);
+                if from_promoted {
+                    self.promoted |= to_sq.bitboard();
+                }
+                if pushed_promoted {
+                    self.promoted |= push_sq.bitboard();
+                }
+                // the pushed piece cannot immediately capture the sentry
+                self.disable_from_sq = push_sq;
+                self.disable_to_sq = to_sq;
+                has_disabled_move = true;
+            } else {
+                let capture_sq = self.capture_square(mv);
+                let captured = self.piece_at_square(capture_sq);
+                self.promoted &= !(from_sq.bitboard() | capture_sq.bitboard());
+                self.remove(from_sq);
+                self.remove(capture_sq);
+                let prom_fig = mv.promotion_figure();
+                if prom_fig != NO_FIGURE {
+                    self.put(to_sq, color_figure(col, prom_fig));
+                    if fig == PAWN || from_promoted {
+                        self.promoted |= to_sq.bitboard();
+                    }
+                } else {
+                    self.put(to_sq, fromp);
+                    if from_promoted {
+                        self.promoted |= to_sq.bitboard();
+                    }
+                }
+                if fig == PAWN {
+                    reset_clock = true;
+                    if (to_sq.rank() as isize - from_sq.rank() as isize).abs() == 2 {
+                        let middle_sq =
+                            rank_file((from_sq.rank() + to_sq.rank()) / 2, from_sq.file());
+                        if PAWN_INFOS[col][middle_sq].captures & self.by_figure[col.inverse()][PAWN]
+                            != 0
+                        {
+                            ep_square = middle_sq;
+                        }
+                    }
+                }
+                if captured != NO_PIECE {
+                    reset_clock = true;
+                    if self.variant == VARIANT_ATOMIC {
+                        self.explode(to_sq);
+                    }
+                }
+            }
+            self.update_castling_rights();
+        }
+
+        self.ep_square = ep_square;
+        self.has_disabled_move = has_disabled_move;
+
+        if reset_clock {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        if col == BLACK {
+            self.fullmove_number += 1;
+        }
+
+        self.turn = col.inverse();
+        self.hash ^= self.extras_hash();
     }
 }
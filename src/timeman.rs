@@ -0,0 +1,105 @@
+use crate::constants::*;
+use crate::piece::*;
+
+/// DEFAULT_MOVES_TO_GO tells the number of moves the remaining time is split into without movestogo
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+
+/// HARD_LIMIT_FACTOR tells how many times the soft limit a search may take at most
+const HARD_LIMIT_FACTOR: u64 = 4;
+
+/// MISSING_CLOCK_MOVETIME tells the time per move in milliseconds when only the clock of the opponent is given
+const MISSING_CLOCK_MOVETIME: u64 = 1000;
+
+/// MAX_INSTABILITY caps how many extra soft limits an unstable best move may add
+const MAX_INSTABILITY: f64 = 1.5;
+
+/// TimeLimits records the clock parameters of a go command, in milliseconds, 0 means not given
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TimeLimits {
+    pub wtime: u64,
+    pub btime: u64,
+    pub winc: u64,
+    pub binc: u64,
+    pub movestogo: u64,
+    pub movetime: u64,
+    /// move overhead tells the time reserved per move for communication lag
+    pub move_overhead: u64,
+}
+
+/// TimeManager decides how long a search may take
+///
+/// the soft limit is the time after which no new iteration is started, it is stretched while the best
+/// move is unstable, the hard limit stops the search in the middle of an iteration
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeManager {
+    /// soft limit in milliseconds, 0 means no limit
+    pub soft_limit: u64,
+    /// hard limit in milliseconds, 0 means no limit
+    pub hard_limit: u64,
+    /// instability is raised by best move changes and decays with each iteration
+    instability: f64,
+}
+
+/// TimeManager implementation
+impl TimeManager {
+    /// returns a new TimeManager for the clock of color, without time parameters the search is not timed,
+    /// if only the clock of the opponent is given a fixed move time is used
+    pub fn new(limits: &TimeLimits, col: Color) -> TimeManager {
+        let (time, inc) = if col == WHITE {
+            (limits.wtime, limits.winc)
+        } else {
+            (limits.btime, limits.binc)
+        };
+        let (soft_limit, hard_limit) = if limits.movetime > 0 {
+            let movetime = std::cmp::max(1, limits.movetime.saturating_sub(limits.move_overhead));
+            (movetime, movetime)
+        } else if time > 0 {
+            let safe = std::cmp::max(1, time.saturating_sub(limits.move_overhead));
+            let moves_to_go = if limits.movestogo > 0 {
+                limits.movestogo
+            } else {
+                DEFAULT_MOVES_TO_GO
+            };
+            // with more moves to go keep a reserve, the last move before the time control may use it all
+            let max_time = if moves_to_go > 1 { safe * 3 / 4 } else { safe };
+            let soft = safe / moves_to_go + inc * 3 / 4;
+            let hard = std::cmp::max(1, std::cmp::min(soft * HARD_LIMIT_FACTOR, max_time));
+            (std::cmp::max(1, std::cmp::min(soft, hard)), hard)
+        } else if limits.wtime > 0 || limits.btime > 0 {
+            let movetime = std::cmp::max(
+                1,
+                MISSING_CLOCK_MOVETIME.saturating_sub(limits.move_overhead),
+            );
+            (movetime, movetime)
+        } else {
+            (0, 0)
+        };
+        TimeManager {
+            soft_limit,
+            hard_limit,
+            instability: 0.0,
+        }
+    }
+
+    /// records the end of an iteration, best move changed tells whether it found a new best move
+    pub fn iteration_done(&mut self, best_move_changed: bool) {
+        self.instability /= 2.0;
+        if best_move_changed {
+            self.instability = (self.instability + 1.0).min(MAX_INSTABILITY);
+        }
+    }
+
+    /// tells whether a new iteration may be started after elapsed milliseconds
+    pub fn can_start_iteration(&self, elapsed: u64) -> bool {
+        if self.soft_limit == 0 {
+            return true;
+        }
+        let soft = (self.soft_limit as f64 * (1.0 + self.instability)) as u64;
+        elapsed < std::cmp::min(soft, self.hard_limit)
+    }
+
+    /// tells whether the search has to stop right away after elapsed milliseconds
+    pub fn hard_limit_reached(&self, elapsed: u64) -> bool {
+        self.hard_limit > 0 && elapsed >= self.hard_limit
+    }
+}
@@ -0,0 +1,201 @@
+use crate::constants::*;
+use crate::piece::*;
+use crate::square::*;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// TT_DEPTH_SHIFT tells the shift of the depth in the data word of a TtSlot
+const TT_DEPTH_SHIFT: usize = 16;
+/// TT_BOUND_SHIFT tells the shift of the bound in the data word of a TtSlot
+const TT_BOUND_SHIFT: usize = 24;
+/// TT_AGE_SHIFT tells the shift of the age in the data word of a TtSlot
+const TT_AGE_SHIFT: usize = 26;
+/// TT_AGE_MASK masks the age, ages wrap around
+const TT_AGE_MASK: u64 = 0x3f;
+/// TT_VALID_BIT marks a data word as holding an entry, so that an empty slot is all zeros
+const TT_VALID_BIT: u64 = 1 << 32;
+
+/// Bound tells how the score of a transposition table entry relates to the true score
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound {
+    /// the score is exact
+    Exact,
+    /// the true score is at least the score, the search failed high
+    Lower,
+    /// the true score is at most the score, the search failed low
+    Upper,
+}
+
+/// TtEntry records what a search found out about a position
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TtEntry {
+    /// mv is the best move, 0 if unknown
+    pub mv: Move,
+    /// score is mate adjusted to be relative to the position, see score_to_tt
+    pub score: Score,
+    pub depth: usize,
+    pub bound: Bound,
+    pub age: u64,
+}
+
+/// TtSlot stores a TtEntry as atomic words, so that the table can be shared between search threads
+///
+/// the key word is the position hash xored with the move and data words, so that a torn write by
+/// another thread fails verification instead of returning a wrong entry
+struct TtSlot {
+    key: AtomicU64,
+    mv: AtomicU64,
+    data: AtomicU64,
+}
+
+/// TransTable is a transposition table keyed by position hash, it can be shared between threads
+pub struct TransTable {
+    slots: Vec<TtSlot>,
+    age: AtomicU64,
+}
+
+/// returns a search score as score to be stored in the transposition table, mate scores become
+/// relative to the position instead of the root, so that they stay valid at any ply
+pub fn score_to_tt(score: Score, ply: usize) -> Score {
+    if score >= MATE_BOUND {
+        score + ply as Score
+    } else if score <= -MATE_BOUND {
+        score - ply as Score
+    } else {
+        score
+    }
+}
+
+/// returns a score stored in the transposition table as search score at ply, undoing score_to_tt
+pub fn score_from_tt(score: Score, ply: usize) -> Score {
+    if score >= MATE_BOUND {
+        score - ply as Score
+    } else if score <= -MATE_BOUND {
+        score + ply as Score
+    } else {
+        score
+    }
+}
+
+/// TransTable implementation
+impl TransTable {
+    /// returns a new empty TransTable using at most size mib MiB, at least one slot is allocated
+    pub fn new(size_mib: usize) -> TransTable {
+        TransTable::try_new(size_mib).expect("could not allocate transposition table")
+    }
+
+    /// returns a new empty TransTable using at most size mib MiB, an error if the memory cannot be allocated
+    pub fn try_new(size_mib: usize) -> Result<TransTable, String> {
+        let slot_size = std::mem::size_of::<TtSlot>();
+        let num_slots = std::cmp::max(1, size_mib.saturating_mul(1 << 20) / slot_size);
+        let mut slots = Vec::new();
+        if slots.try_reserve_exact(num_slots).is_err() {
+            return Err(format!("could not allocate {} MiB for hash", size_mib));
+        }
+        slots.resize_with(num_slots, || TtSlot {
+            key: AtomicU64::new(0),
+            mv: AtomicU64::new(0),
+            data: AtomicU64::new(0),
+        });
+        Ok(TransTable {
+            slots,
+            age: AtomicU64::new(0),
+        })
+    }
+
+    /// returns the number of slots
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// tells whether there are no slots, never true as at least one is allocated
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// removes all entries
+    pub fn clear(&self) {
+        for slot in self.slots.iter() {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.mv.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    /// starts a new search, entries of earlier searches get replaced first
+    pub fn new_search(&self) {
+        self.age.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// returns the age of the current search
+    pub fn age(&self) -> u64 {
+        self.age.load(Ordering::Relaxed) & TT_AGE_MASK
+    }
+
+    /// returns the slot for a hash
+    fn slot(&self, hash: u64) -> &TtSlot {
+        &self.slots[(hash % (self.slots.len() as u64)) as usize]
+    }
+
+    /// returns the hash and the entry stored in a slot, None if the slot is empty
+    fn read(slot: &TtSlot) -> Option<(u64, TtEntry)> {
+        let mv = slot.mv.load(Ordering::Relaxed);
+        let data = slot.data.load(Ordering::Relaxed);
+        if data & TT_VALID_BIT == 0 {
+            return None;
+        }
+        let entry = TtEntry {
+            mv,
+            score: (data & 0xffff) as u16 as i16 as Score,
+            depth: ((data >> TT_DEPTH_SHIFT) & 0xff) as usize,
+            bound: match (data >> TT_BOUND_SHIFT) & 0x3 {
+                1 => Bound::Lower,
+                2 => Bound::Upper,
+                _ => Bound::Exact,
+            },
+            age: (data >> TT_AGE_SHIFT) & TT_AGE_MASK,
+        };
+        Some((slot.key.load(Ordering::Relaxed) ^ mv ^ data, entry))
+    }
+
+    /// returns the entry for hash, None if the position is not in the table
+    pub fn probe(&self, hash: u64) -> Option<TtEntry> {
+        match TransTable::read(self.slot(hash)) {
+            Some((slot_hash, entry)) if slot_hash == hash => Some(entry),
+            _ => None,
+        }
+    }
+
+    /// stores an entry for hash, the score has to be converted with score_to_tt
+    ///
+    /// an entry of another position from the current search is only replaced by a search that is not
+    /// much shallower, the best move of the same position is kept if the new entry has none
+    pub fn store(&self, hash: u64, mv: Move, score: Score, depth: usize, bound: Bound) {
+        let age = self.age();
+        let slot = self.slot(hash);
+        let mut mv = mv;
+        if let Some((slot_hash, old)) = TransTable::read(slot) {
+            if slot_hash == hash {
+                if mv == 0 {
+                    mv = old.mv;
+                }
+            } else if old.age == age && old.depth > depth + 2 {
+                return;
+            }
+        }
+        let bound_bits: u64 = match bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        let data = (score as i16 as u16 as u64)
+            | ((std::cmp::min(depth, 0xff) as u64) << TT_DEPTH_SHIFT)
+            | (bound_bits << TT_BOUND_SHIFT)
+            | (age << TT_AGE_SHIFT)
+            | TT_VALID_BIT;
+        slot.key.store(hash ^ mv ^ data, Ordering::Relaxed);
+        slot.mv.store(mv, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+}
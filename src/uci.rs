@@ -1,16 +1,42 @@
+use crate::betza::*;
 use crate::bitboard::*;
+use crate::bughouse::*;
 use crate::constants::*;
+use crate::history::*;
 use crate::lineargame::*;
+use crate::perftsuite::*;
 use crate::piece::*;
+use crate::search::*;
 use crate::square::*;
-//use crate::state::*;
+use crate::state::*;
+use crate::transtable::*;
 
 use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
 
 pub struct Uci {
     pub engine_name: String,
     pub engine_author: String,
     pub linear_game: LinearGame,
+    pub bughouse: BughouseGame,
+    pub tt: Arc<TransTable>,
+    pub move_overhead: u64,
+    pub threads: usize,
+    pub search_options: SearchOptions,
+    /// stop tells the running search to stop
+    stop: Arc<AtomicBool>,
+    /// histories keeps the move ordering history of the search threads between searches
+    histories: Arc<Mutex<Vec<History>>>,
+    search_thread: Option<thread::JoinHandle<()>>,
+}
+
+/// returns the locked histories, a search thread that panicked leaves them usable
+fn lock_histories(histories: &Mutex<Vec<History>>) -> MutexGuard<'_, Vec<History>> {
+    histories
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
 }
 
 pub fn create_default_uci() -> Uci {
@@ -18,6 +44,14 @@ pub fn create_default_uci() -> Uci {
         engine_name: "rustengine".to_string(),
         engine_author: "easychessanimations".to_string(),
         linear_game: LinearGame::new(),
+        bughouse: BughouseGame::new(),
+        tt: Arc::new(TransTable::new(DEFAULT_HASH_MIB)),
+        move_overhead: DEFAULT_MOVE_OVERHEAD,
+        threads: 1,
+        search_options: SearchOptions::new(),
+        stop: Arc::new(AtomicBool::new(false)),
+        histories: Arc::new(Mutex::new(Vec::new())),
+        search_thread: None,
     };
     uci.linear_game.init(DEFAULT_VARIANT);
     uci
@@ -135,6 +169,25 @@ impl Uci {
     pub fn execute_uci_command(&self) {
         println!("id name {}", self.engine_name);
         println!("id author {}\n", self.engine_author);
+        println!(
+            "option name Hash type spin default {} min 1 max {}",
+            DEFAULT_HASH_MIB, MAX_HASH_MIB
+        );
+        println!(
+            "option name Move Overhead type spin default {} min 0 max {}",
+            DEFAULT_MOVE_OVERHEAD, MAX_MOVE_OVERHEAD
+        );
+        println!(
+            "option name Threads type spin default 1 min 1 max {}",
+            MAX_THREADS
+        );
+        println!(
+            "option name MultiPV type spin default 1 min 1 max {}",
+            MAX_MULTI_PV
+        );
+        for name in SEARCH_OPTION_NAMES.iter() {
+            println!("option name {} type check default true", name);
+        }
         println!("uciok");
     }
 
@@ -143,10 +196,35 @@ impl Uci {
 
         let command = parts[0];
 
+        if command.is_empty() {
+            return true;
+        }
+
+        if command == "isready" {
+            println!("readyok");
+
+            return true;
+        }
+
+        // the running search stops before any other command is processed
+        self.stop_search();
+
         if command == "quit" || command == "q" || command == "exit" || command == "x" {
             return false;
         }
 
+        if command == "stop" {
+            return true;
+        }
+
+        if command == "position" {
+            if let Err(err) = self.process_position_command(&parts[1..]) {
+                println!("{}", err);
+            }
+
+            return true;
+        }
+
         if command == "uci" {
             self.execute_uci_command();
             return true;
@@ -160,7 +238,7 @@ impl Uci {
         if command == "demo" {
             let mut arg = "";
 
-            if parts.len() > 0 {
+            if parts.len() > 1 {
                 arg = parts[1];
             }
 
@@ -174,18 +252,191 @@ impl Uci {
             return true;
         }
 
+        if (command == "perft" && parts.get(1) == Some(&"divide"))
+            || (command == "go" && parts.get(1) == Some(&"perft"))
+        {
+            match parts.get(2).map(|depth| depth.parse::<usize>()) {
+                Some(Ok(depth)) if depth > 0 => self.perft_divide(depth),
+                _ => println!("usage : perft divide <depth> | go perft <depth>"),
+            }
+
+            return true;
+        }
+
+        if command == "setoption" {
+            if let Err(err) = self.process_setoption_command(&parts[1..]) {
+                println!("{}", err);
+            }
+
+            return true;
+        }
+
+        if command == "ucinewgame" {
+            self.tt.clear();
+            lock_histories(&self.histories).clear();
+
+            return true;
+        }
+
+        if command == "go" {
+            self.go(&parts[1..]);
+
+            return true;
+        }
+
+        if command == "perft" && parts.get(1) == Some(&"stats") {
+            match parts.get(2).map(|depth| depth.parse::<usize>()) {
+                Some(Ok(depth)) => self.perft_stats(depth),
+                _ => println!("usage : perft stats <depth>"),
+            }
+            return true;
+        }
+
+        if command == "perftsuite" {
+            match parts.get(1) {
+                Some(path) => self.perft_suite(path),
+                None => println!("usage : perftsuite <file>"),
+            }
+
+            return true;
+        }
+
         if command == "perft" || command == "p" {
             let depth = if command == "p" {
                 4
             } else {
-                parts[1].parse().expect("illegal perft depth")
+                match parts.get(1).map(|depth| depth.parse::<usize>()) {
+                    Some(Ok(depth)) => depth,
+                    _ => {
+                        println!(
+                            "usage : perft <depth> [threads [hash mib]] | perft divide <depth>"
+                        );
+                        return true;
+                    }
+                }
             };
+
+            if let Some(Ok(threads)) = parts.get(2).map(|threads| threads.parse::<usize>()) {
+                let hash_mib = match parts.get(3).map(|hash_mib| hash_mib.parse::<usize>()) {
+                    None => 0,
+                    Some(Ok(hash_mib)) => hash_mib,
+                    Some(Err(_)) => {
+                        println!("illegal perft hash size {}", parts[3]);
+                        return true;
+                    }
+                };
+
+                let perft_result = self.linear_game.perft_threaded(depth, threads, hash_mib);
+
+                println!(
+                    "thread(s) {} hash {} MiB node(s) {:?} , time {:.2} sec(s) , nps {} kNode(s)/sec",
+                    threads, hash_mib, perft_result.0, perft_result.1, perft_result.2
+                );
+
+                return true;
+            }
+
             let perft_result = self.linear_game.perft(depth);
 
             println!(
-                "node(s) {:?} , time {:.2} sec(s) , nps {} kNode(s)/sec",
+                "make/unmake node(s) {:?} , time {:.2} sec(s) , nps {} kNode(s)/sec",
                 perft_result.0, perft_result.1, perft_result.2
             );
+
+            let clone_result = match self.linear_game.perft_clone(depth) {
+                Ok(clone_result) => clone_result,
+                Err(err) => {
+                    println!("{}", err);
+                    return true;
+                }
+            };
+
+            println!(
+                "clone       node(s) {:?} , time {:.2} sec(s) , nps {} kNode(s)/sec",
+                clone_result.0, clone_result.1, clone_result.2
+            );
+
+            println!(
+                "make/unmake speedup {:.2}x",
+                clone_result.1 / perft_result.1.max(1e-6)
+            );
+
+            return true;
+        }
+
+        if command == "betza" {
+            if parts.len() < 2 {
+                println!("usage : betza <notation> [square]");
+                return true;
+            }
+
+            let sq = if parts.len() > 2 {
+                Square::from_uci(parts[2].to_string())
+            } else {
+                (SQUARE_E4, true)
+            };
+
+            match BetzaPiece::new(parts[1], parts[1]) {
+                Ok(piece) if sq.1 => println!("{}", piece.pretty_print_string(sq.0)),
+                Ok(_) => println!("invalid square {}", parts[2]),
+                Err(err) => println!("{}", err),
+            }
+
+            return true;
+        }
+
+        if command == "pieces" {
+            let path = if parts.len() > 1 {
+                parts[1]
+            } else {
+                "pieces.txt"
+            };
+
+            match load_betza_pieces(path).and_then(|pieces| {
+                register_custom_pieces(&pieces)?;
+                Ok(pieces)
+            }) {
+                Ok(pieces) => {
+                    for piece in pieces.iter() {
+                        println!("{}", piece.pretty_print_string(SQUARE_E4));
+                    }
+                    // the current position may hold pieces of the previous definitions
+                    let variant = self.linear_game.current().variant();
+                    self.linear_game.init(variant);
+                    self.tt.clear();
+                    lock_histories(&self.histories).clear();
+                }
+                Err(err) => println!("{}", err),
+            }
+
+            return true;
+        }
+
+        if command == "variant" || command == "v" {
+            match parts.get(1).and_then(|name| variant_from_name(name)) {
+                Some(variant) => {
+                    self.linear_game.init(variant);
+                    self.tt.clear();
+                    lock_histories(&self.histories).clear();
+                    self.linear_game.print();
+                }
+                None => println!(
+                    "usage : variant <{}>",
+                    VARIANT_INFOS
+                        .iter()
+                        .map(|vi| vi.display_name.to_lowercase())
+                        .collect::<Vec<String>>()
+                        .join("|")
+                ),
+            }
+
+            return true;
+        }
+
+        if command == "bug" {
+            self.process_bughouse_command(&parts[1..]);
+
+            return true;
         }
 
         if command == "bb" {
@@ -199,20 +450,324 @@ impl Uci {
             self.linear_game.print();
         }
 
-        match command.parse::<usize>() {
-            Ok(n) => {
-                if n > 0 {
-                    self.linear_game.push_by_index(n - 1);
-                    self.linear_game.print();
+        let (mv, ok) = self.linear_game.current().move_from_uci(command);
+
+        if ok {
+            match self.linear_game.push(mv) {
+                Ok(()) => self.linear_game.print(),
+                Err(err) => println!("{}", err),
+            }
+            return true;
+        }
+
+        if let Ok(n) = command.parse::<usize>() {
+            if n > 0 {
+                match self.linear_game.push_by_index(n - 1) {
+                    Ok(()) => self.linear_game.print(),
+                    Err(err) => println!("{}", err),
                 }
-                return true;
             }
-            Err(_) => (),
+            return true;
         }
 
         true
     }
 
+    /// sets an option, arguments : name <name> value <value>, option names are case insensitive
+    pub fn process_setoption_command(&mut self, args: &[&str]) -> Result<(), String> {
+        let value_index = args.iter().position(|arg| *arg == "value");
+        if args.first() != Some(&"name") || value_index.is_none() {
+            return Err("usage : setoption name <name> value <value>".to_string());
+        }
+        let value_index = value_index.unwrap_or(0);
+        let name = args[1..value_index].join(" ").to_lowercase();
+        let value = args[value_index + 1..].join(" ");
+        let spin = |min: usize, max: usize| match value.parse::<usize>() {
+            Ok(n) if n >= min && n <= max => Ok(n),
+            _ => Err(format!(
+                "invalid value {:?} for option {}, expected {} to {}",
+                value, name, min, max
+            )),
+        };
+        let check = || match value.to_lowercase().as_str() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(format!(
+                "invalid value {:?} for option {}, expected true or false",
+                value, name
+            )),
+        };
+        match name.as_str() {
+            "hash" => {
+                let size_mib = spin(1, MAX_HASH_MIB)?;
+                // drop the old table first, so that both never have to fit into memory
+                self.tt = Arc::new(TransTable::new(0));
+                match TransTable::try_new(size_mib) {
+                    Ok(tt) => self.tt = Arc::new(tt),
+                    Err(err) => {
+                        self.tt = Arc::new(TransTable::new(DEFAULT_HASH_MIB));
+                        return Err(format!("{}, using {} MiB", err, DEFAULT_HASH_MIB));
+                    }
+                }
+            }
+            "threads" => self.threads = spin(1, MAX_THREADS)?,
+            "move overhead" => self.move_overhead = spin(0, MAX_MOVE_OVERHEAD as usize)? as u64,
+            "multipv" => self.search_options.multi_pv = spin(1, MAX_MULTI_PV)?,
+            "nullmove" => self.search_options.null_move = check()?,
+            "lmr" => self.search_options.lmr = check()?,
+            "futility" => self.search_options.futility = check()?,
+            "reversefutility" => self.search_options.reverse_futility = check()?,
+            "razoring" => self.search_options.razoring = check()?,
+            _ => return Err(format!("unknown option {}", name)),
+        }
+        Ok(())
+    }
+
+    /// sets up the position, arguments : startpos | fen <fen> , optionally followed by moves <moves>
+    ///
+    /// the variant stays the current one, moves are in UCI notation,
+    /// the position is left unchanged if the fen or a move is invalid
+    pub fn process_position_command(&mut self, args: &[&str]) -> Result<(), String> {
+        let variant = self.linear_game.current().variant();
+        let moves_index = args
+            .iter()
+            .position(|arg| *arg == "moves")
+            .unwrap_or(args.len());
+        let mut game = LinearGame::new();
+        match args.first() {
+            Some(&"startpos") => game.init(variant),
+            Some(&"fen") if moves_index > 1 => {
+                game.init_from_fen(variant, &args[1..moves_index].join(" "))?
+            }
+            _ => return Err("usage : position startpos | fen <fen> [moves <moves>]".to_string()),
+        }
+        for uci in args.iter().skip(moves_index + 1) {
+            let (mv, ok) = game.current().move_from_uci(uci);
+            if !ok {
+                return Err(format!("illegal move {}", uci));
+            }
+            game.push(mv)?;
+        }
+        self.linear_game = game;
+        Ok(())
+    }
+
+    /// parses the arguments of a go command into search limits
+    ///
+    /// arguments : [depth <n>] [nodes <n>] [movetime <ms>] [wtime <ms>] [btime <ms>] [winc <ms>] [binc <ms>]
+    /// [movestogo <n>] [infinite], without limits the search is infinite, ponder is rejected as there is no Ponder option
+    pub fn parse_go_limits(&self, args: &[&str]) -> Result<SearchLimits, String> {
+        let mut limits = SearchLimits::default();
+        limits.time.move_overhead = self.move_overhead;
+        let mut i = 0;
+        while i < args.len() {
+            if args[i] == "infinite" {
+                limits.infinite = true;
+                i += 1;
+                continue;
+            }
+            if args[i] == "ponder" {
+                return Err("pondering is not supported".to_string());
+            }
+            let value = match args.get(i + 1).map(|value| value.parse::<u64>()) {
+                Some(Ok(value)) => value,
+                _ => return Err(format!("invalid go argument {}", args[i])),
+            };
+            match args[i] {
+                "depth" => limits.depth = value as usize,
+                "nodes" => limits.nodes = value as usize,
+                "movetime" => limits.time.movetime = value,
+                "wtime" => limits.time.wtime = value,
+                "btime" => limits.time.btime = value,
+                "winc" => limits.time.winc = value,
+                "binc" => limits.time.binc = value,
+                "movestogo" => limits.time.movestogo = value,
+                _ => return Err(format!("invalid go argument {}", args[i])),
+            }
+            i += 2;
+        }
+        let time = limits.time;
+        if limits.depth == 0
+            && limits.nodes == 0
+            && time.movetime == 0
+            && time.wtime == 0
+            && time.btime == 0
+        {
+            limits.infinite = true;
+        }
+        Ok(limits)
+    }
+
+    /// starts searching the current position in a search thread, see parse go limits for the arguments
+    ///
+    /// the thread prints info lines and the best move, an infinite search prints the best move once stopped
+    pub fn go(&mut self, args: &[&str]) {
+        let limits = match self.parse_go_limits(args) {
+            Ok(limits) => limits,
+            Err(err) => {
+                println!("{}", err);
+                println!("usage : go [depth <n>] [nodes <n>] [movetime <ms>] [wtime <ms>] [btime <ms>] [winc <ms>] [binc <ms>] [movestogo <n>] [infinite] | go perft <depth>");
+                return;
+            }
+        };
+
+        self.stop_search();
+        self.stop.store(false, Ordering::Relaxed);
+
+        let game = self.linear_game.clone();
+        let tt = self.tt.clone();
+        let stop = self.stop.clone();
+        let histories = self.histories.clone();
+        let threads = self.threads;
+        let options = self.search_options;
+
+        let spawned = thread::Builder::new()
+            .name("search".to_string())
+            .stack_size(SEARCH_STACK_SIZE)
+            .spawn(move || {
+                let mut histories = lock_histories(&histories);
+                let info = search_threaded(
+                    &game,
+                    tt,
+                    stop,
+                    limits,
+                    options,
+                    &mut histories,
+                    threads,
+                    |info| println!("{}", info.uci()),
+                );
+
+                println!(
+                    "info nodes {} nps {} time {}",
+                    info.nodes,
+                    info.nps(),
+                    info.millis
+                );
+
+                match info.best_move() {
+                    0 => println!("bestmove (none)"),
+                    mv => println!("bestmove {}", mv.uci()),
+                }
+            });
+
+        match spawned {
+            Ok(handle) => self.search_thread = Some(handle),
+            Err(err) => println!("could not start search thread : {}", err),
+        }
+    }
+
+    /// stops the running search if any and waits for it to print its best move
+    pub fn stop_search(&mut self) {
+        if let Some(handle) = self.search_thread.take() {
+            self.stop.store(true, Ordering::Relaxed);
+            if handle.join().is_err() {
+                println!("search thread panicked");
+            }
+        }
+    }
+
+    /// waits for the running search to finish on its own, an infinite search has to be stopped
+    pub fn wait_for_search(&mut self) {
+        if let Some(handle) = self.search_thread.take() {
+            if handle.join().is_err() {
+                println!("search thread panicked");
+            }
+        }
+    }
+
+    /// prints the perft node count of each root move and the total
+    pub fn perft_divide(&mut self, depth: usize) {
+        let start = std::time::Instant::now();
+
+        let counts = self.linear_game.perft_divide(depth);
+
+        let mut total = 0;
+
+        for (mv, nodes) in counts.iter() {
+            println!("{}: {}", mv.uci(), nodes);
+            total += nodes;
+        }
+
+        println!(
+            "\nmove(s) {} , total node(s) {} , time {:.2} sec(s)",
+            counts.len(),
+            total,
+            start.elapsed().as_secs_f32()
+        );
+    }
+
+    /// prints the perft stats at depth
+    pub fn perft_stats(&mut self, depth: usize) {
+        let start = std::time::Instant::now();
+        let stats = self.linear_game.perft_stats(depth);
+        for (name, count) in stats.counts().iter() {
+            println!("{:<22} {}", name, count);
+        }
+        println!("time {:.2} sec(s)", start.elapsed().as_secs_f32());
+    }
+
+    /// runs a perft suite from an EPD file and prints the outcome of each check
+    pub fn perft_suite(&self, path: &str) {
+        match load_perft_suite(path) {
+            Ok(entries) => {
+                let result = run_perft_suite(&entries, usize::MAX, true);
+                println!(
+                    "\npassed {} , failed {} , skipped {}",
+                    result.passed, result.failed, result.skipped
+                );
+                for failure in result.failures.iter() {
+                    println!("failed {}", failure);
+                }
+            }
+            Err(err) => println!("{}", err),
+        }
+    }
+
+    /// processes a bughouse command, arguments : none | new | fen <bfen> | a <n> | b <n> | d | use <a|b>
+    pub fn process_bughouse_command(&mut self, args: &[&str]) {
+        let board_index = |name: &str| match name {
+            "a" | "A" => Some(0),
+            "b" | "B" => Some(1),
+            _ => None,
+        };
+
+        match args.first() {
+            Some(&"new") => self.bughouse.init(),
+            Some(&"fen") => {
+                if let Err(err) = self.bughouse.set_from_bfen(&args[1..].join(" ")) {
+                    println!("{}", err);
+                }
+            }
+            Some(&"d") => self.bughouse.pop(),
+            Some(&"use") => {
+                if let Some(board) = args.get(1).and_then(|name| board_index(name)) {
+                    let fen = self.bughouse.boards[board].current().report_fen();
+                    match self.linear_game.init_from_fen(VARIANT_BUGHOUSE, &fen) {
+                        Ok(()) => self.linear_game.print(),
+                        Err(err) => println!("{}", err),
+                    }
+                } else {
+                    println!("usage : bug use <a|b>");
+                }
+                return;
+            }
+            Some(name) if board_index(name).is_some() => {
+                let board = board_index(name).expect("should not happen");
+                match args.get(1).map(|n| n.parse::<usize>()) {
+                    Some(Ok(n)) if n > 0 => {
+                        if let Err(err) = self.bughouse.push_by_index(board, n - 1) {
+                            println!("{}", err);
+                        }
+                    }
+                    _ => println!("usage : bug {} <move index>", name),
+                }
+            }
+            _ => (),
+        }
+
+        self.bughouse.print();
+    }
+
     pub fn welcome(&self, build_info: &str) {
         println!(
             "{} bitboard multi variant uci chess analysis engine by {} [ {} ]",
@@ -228,5 +783,7 @@ impl Uci {
                 break;
             }
         }
+
+        self.stop_search();
     }
 }
@@ -0,0 +1,83 @@
+use crate::constants::*;
+use crate::piece::*;
+use crate::square::*;
+
+use once_cell::sync::Lazy;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// ZOBRIST_SEED is the seed of the random generator of the zobrist keys, fixed so that keys are stable between runs
+pub const ZOBRIST_SEED: u64 = 0x5eed_2020_c0ff_ee00;
+
+/// PIECE_ARRAY_SIZE tells the number of possible piece codes
+pub const PIECE_ARRAY_SIZE: usize = 2 * FIGURE_ARRAY_SIZE;
+
+/// MAX_POCKET_COUNT tells the number of pieces of one kind in a pocket that are hashed separately
+pub const MAX_POCKET_COUNT: usize = 64;
+
+/// ZobristKeys records the random keys that are xored together to form the hash of a state
+pub struct ZobristKeys {
+    /// piece records the keys for piece code and square, lancer directions are separate piece codes
+    pub piece: [[u64; BOARD_AREA]; PIECE_ARRAY_SIZE],
+    /// turn is xored into the hash when white is to move
+    pub turn: u64,
+    /// castling records the keys for color and castling side
+    pub castling: [[u64; 2]; 2],
+    /// ep records the keys for the ep square
+    pub ep: [u64; BOARD_AREA],
+    /// disable_from records the keys for the from square of the Eightpiece disabled move
+    pub disable_from: [u64; BOARD_AREA],
+    /// disable_to records the keys for the to square of the Eightpiece disabled move
+    pub disable_to: [u64; BOARD_AREA],
+    /// pocket records the keys for piece code and number of such pieces in the pocket
+    pub pocket: [[u64; MAX_POCKET_COUNT]; PIECE_ARRAY_SIZE],
+    /// promoted records the keys for squares of promoted pieces, which go to the pocket as pawns
+    pub promoted: [u64; BOARD_AREA],
+}
+
+/// ZOBRIST holds the zobrist keys
+pub static ZOBRIST: Lazy<ZobristKeys> = Lazy::new(|| {
+    let mut rng = StdRng::seed_from_u64(ZOBRIST_SEED);
+    let mut keys = ZobristKeys {
+        piece: [[0; BOARD_AREA]; PIECE_ARRAY_SIZE],
+        turn: rng.gen::<u64>(),
+        castling: [[0; 2]; 2],
+        ep: [0; BOARD_AREA],
+        disable_from: [0; BOARD_AREA],
+        disable_to: [0; BOARD_AREA],
+        pocket: [[0; MAX_POCKET_COUNT]; PIECE_ARRAY_SIZE],
+        promoted: [0; BOARD_AREA],
+    };
+    for p in 0..PIECE_ARRAY_SIZE {
+        for sq in 0..BOARD_AREA {
+            keys.piece[p][sq] = rng.gen::<u64>();
+        }
+        // an empty pocket hashes to zero
+        for count in 1..MAX_POCKET_COUNT {
+            keys.pocket[p][count] = rng.gen::<u64>();
+        }
+    }
+    for col in BLACK..WHITE + 1 {
+        for side in KING_SIDE..QUEEN_SIDE + 1 {
+            keys.castling[col][side] = rng.gen::<u64>();
+        }
+    }
+    for sq in 0..BOARD_AREA {
+        keys.ep[sq] = rng.gen::<u64>();
+        keys.disable_from[sq] = rng.gen::<u64>();
+        keys.disable_to[sq] = rng.gen::<u64>();
+        keys.promoted[sq] = rng.gen::<u64>();
+    }
+    keys
+});
+
+/// returns the zobrist key of a piece on a square
+pub fn piece_key(p: Piece, sq: Square) -> u64 {
+    ZOBRIST.piece[p][sq]
+}
+
+/// returns the zobrist key of count pieces of a kind in the pocket
+pub fn pocket_key(p: Piece, count: usize) -> u64 {
+    ZOBRIST.pocket[p][std::cmp::min(count, MAX_POCKET_COUNT - 1)]
+}